@@ -0,0 +1,2 @@
+pub mod service_auth;
+pub mod service_signature;
\ No newline at end of file
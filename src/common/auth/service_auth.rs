@@ -0,0 +1,106 @@
+use super::service_signature::{verify_request, USER_ID_HEADER};
+use crate::common::settings::app_settings::app_settings;
+use actix_http::h1;
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error::ErrorUnauthorized,
+    http::Method,
+    web::Bytes,
+    Error, HttpRequest,
+};
+use actix_web_lab::middleware::Next;
+use log::warn;
+
+/// the probes and the metrics scrape were open to the cluster
+const PUBLIC_PATH_PREFIXES: [&str; 2] = ["/texhub/actuator/", "/metrics"];
+
+fn is_public_path(path: &str) -> bool {
+    return PUBLIC_PATH_PREFIXES.iter().any(|p| path.starts_with(p));
+}
+
+/**
+ * reject the request without a valid signature of the shared secret when `cv.service_auth_mode` was hmac
+ * the GET body was not read, the websocket upgrade keeps its payload
+ */
+pub async fn verify_service_auth(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let cv = &app_settings().cv;
+    if cv.service_auth_mode == "none" || is_public_path(req.path()) {
+        return next.call(req).await;
+    }
+    let read_body = req.method() != Method::GET;
+    let body = if read_body {
+        req.extract::<Bytes>().await?
+    } else {
+        Bytes::new()
+    };
+    let verified = verify_request(
+        req.request(),
+        &body,
+        &cv.service_auth_secret,
+        cv.service_auth_max_skew_secs,
+    );
+    if let Err(e) = verified {
+        warn!("reject the request: {}, path: {}", e, req.path());
+        return Err(ErrorUnauthorized(e));
+    }
+    if read_body {
+        // put the body back for the handler
+        let (_, mut payload) = h1::Payload::create(true);
+        payload.unread_data(body);
+        req.set_payload(payload.into());
+    }
+    return next.call(req).await;
+}
+
+/**
+ * the user the request acts for, required in hmac mode where it was a part of the signature
+ * None in none mode, the unsigned header could be anyone
+ */
+pub fn signed_user_id(req: &HttpRequest) -> Result<Option<i64>, String> {
+    if app_settings().cv.service_auth_mode != "hmac" {
+        return Ok(None);
+    }
+    let user_id = req
+        .headers()
+        .get(USER_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or("missing user-id")?;
+    let user_id = user_id
+        .parse::<i64>()
+        .map_err(|_| format!("invalid user-id {}", user_id))?;
+    return Ok(Some(user_id));
+}
+
+/**
+ * the compile job of the request should be owned by the signed user, the absent owner was taken from it
+ */
+pub fn bind_job_owner(owner: i64, signed_user: Option<i64>) -> Result<i64, String> {
+    match signed_user {
+        None => return Ok(owner),
+        Some(uid) if owner == 0 || owner == uid => return Ok(uid),
+        Some(uid) => {
+            return Err(format!(
+                "user {} could not act on the job of user {}",
+                uid, owner
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_job_was_bound_to_the_signed_user() {
+        assert_eq!(bind_job_owner(7, None), Ok(7));
+        assert_eq!(bind_job_owner(0, None), Ok(0));
+        assert_eq!(bind_job_owner(0, Some(7)), Ok(7));
+        assert_eq!(bind_job_owner(7, Some(7)), Ok(7));
+        assert!(bind_job_owner(8, Some(7)).is_err());
+    }
+}
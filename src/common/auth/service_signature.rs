@@ -0,0 +1,141 @@
+use crate::common::settings::app_settings::app_settings;
+use actix_web::HttpRequest;
+use hmac::{Hmac, Mac};
+use log::error;
+use reqwest::{header::HeaderValue, Request, RequestBuilder, Response};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const TIMESTAMP_HEADER: &str = "x-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-signature";
+/// the acting user of the call, signed together with the request
+pub const USER_ID_HEADER: &str = "user-id";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn now_secs() -> i64 {
+    return SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+}
+
+/**
+ * the signed content, the method, the path with the query, the timestamp,
+ * the acting user id and the sha256 of the body, one per line
+ */
+fn canonical_request(
+    method: &str,
+    path_and_query: &str,
+    timestamp: &str,
+    user_id: &str,
+    body: &[u8],
+) -> String {
+    let body_hash = hex::encode(Sha256::digest(body));
+    return format!(
+        "{}\n{}\n{}\n{}\n{}",
+        method, path_and_query, timestamp, user_id, body_hash
+    );
+}
+
+fn new_mac(secret: &str) -> HmacSha256 {
+    // the hmac accepts the key of any length
+    return HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+}
+
+/**
+ * sign the outgoing request with the shared secret, skipped when the secret was not set
+ * the user id header should be set before, it was a part of the signature
+ */
+pub fn sign_request(req: &mut Request) {
+    let secret = &app_settings().cv.service_auth_secret;
+    if secret.is_empty() {
+        return;
+    }
+    let timestamp = now_secs().to_string();
+    let path_and_query = match req.url().query() {
+        Some(q) => format!("{}?{}", req.url().path(), q),
+        None => req.url().path().to_owned(),
+    };
+    let user_id = req
+        .headers()
+        .get(USER_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+    // the streaming body was not used by the clients
+    let body = req.body().and_then(|b| b.as_bytes()).unwrap_or_default();
+    let canonical = canonical_request(
+        req.method().as_str(),
+        &path_and_query,
+        &timestamp,
+        &user_id,
+        body,
+    );
+    let mut mac = new_mac(secret);
+    mac.update(canonical.as_bytes());
+    let signature = hex::encode(mac.finalize().into_bytes());
+    match (
+        HeaderValue::from_str(&timestamp),
+        HeaderValue::from_str(&signature),
+    ) {
+        (Ok(t), Ok(s)) => {
+            req.headers_mut().insert(TIMESTAMP_HEADER, t);
+            req.headers_mut().insert(SIGNATURE_HEADER, s);
+        }
+        _ => error!("build the signature headers failed, url: {}", req.url()),
+    }
+}
+
+/**
+ * sign and send the request on the client it was built from
+ */
+pub async fn send_signed(builder: RequestBuilder) -> Result<Response, reqwest::Error> {
+    let (client, req) = builder.build_split();
+    let mut req = req?;
+    sign_request(&mut req);
+    return client.execute(req).await;
+}
+
+fn header_str<'a>(req: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    return req.headers().get(name).and_then(|v| v.to_str().ok());
+}
+
+/**
+ * check the signature of the incoming request, the timestamp out of the allowed skew was rejected
+ */
+pub fn verify_request(
+    req: &HttpRequest,
+    body: &[u8],
+    secret: &str,
+    max_skew_secs: u64,
+) -> Result<(), String> {
+    let timestamp = header_str(req, TIMESTAMP_HEADER).ok_or("missing timestamp")?;
+    let signature = header_str(req, SIGNATURE_HEADER).ok_or("missing signature")?;
+    let ts: i64 = timestamp
+        .parse()
+        .map_err(|_| format!("invalid timestamp {}", timestamp))?;
+    if (now_secs() - ts).unsigned_abs() > max_skew_secs {
+        return Err(format!("timestamp {} out of the allowed skew", timestamp));
+    }
+    let signature = hex::decode(signature).map_err(|_| "invalid signature".to_owned())?;
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or(req.path());
+    let user_id = header_str(req, USER_ID_HEADER).unwrap_or_default();
+    let canonical = canonical_request(
+        req.method().as_str(),
+        path_and_query,
+        timestamp,
+        user_id,
+        body,
+    );
+    let mut mac = new_mac(secret);
+    mac.update(canonical.as_bytes());
+    // compared in constant time
+    return mac
+        .verify_slice(&signature)
+        .map_err(|_| "signature mismatch".to_owned());
+}
@@ -0,0 +1,3 @@
+pub mod redis_async;
+pub mod redis_lock;
+pub mod redis_pool;
\ No newline at end of file
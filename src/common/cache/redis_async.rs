@@ -0,0 +1,51 @@
+use crate::common::settings::app_settings::app_settings;
+use redis::{
+    aio::{ConnectionManager, MultiplexedConnection},
+    ErrorKind, RedisError, RedisResult,
+};
+use std::{future::Future, sync::OnceLock, time::Duration};
+use tokio::{sync::OnceCell, time::timeout};
+
+/// connecting longer than this was treated as redis down
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn redis_client() -> &'static redis::Client {
+    static CLIENT: OnceLock<redis::Client> = OnceLock::new();
+    // the url was validated at startup
+    CLIENT.get_or_init(|| redis::Client::open(app_settings().redis_url.as_str()).unwrap())
+}
+
+async fn connect<T, F>(fut: F) -> RedisResult<T>
+where
+    F: Future<Output = RedisResult<T>>,
+{
+    match timeout(CONNECT_TIMEOUT, fut).await {
+        Ok(result) => return result,
+        Err(_) => {
+            return Err(RedisError::from((
+                ErrorKind::IoError,
+                "connect redis timed out",
+            )))
+        }
+    }
+}
+
+/**
+ * a new connection owned by the caller, for the `XREAD BLOCK` of the log subscribers
+ * kept out of the pool so the idle viewers could not starve the compiles, closed when dropped
+ */
+pub async fn dedicated_redis() -> RedisResult<MultiplexedConnection> {
+    return connect(redis_client().get_multiplexed_async_connection()).await;
+}
+
+/**
+ * the connection shared by the short commands of the compile jobs, reconnected when it broke
+ * the blocking commands must not run on it, they would hold up every other caller
+ */
+pub async fn shared_redis() -> RedisResult<ConnectionManager> {
+    static SHARED: OnceCell<ConnectionManager> = OnceCell::const_new();
+    let con = SHARED
+        .get_or_try_init(|| connect(ConnectionManager::new(redis_client().clone())))
+        .await?;
+    return Ok(con.clone());
+}
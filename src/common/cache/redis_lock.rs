@@ -0,0 +1,55 @@
+use crate::common::settings::app_settings::app_settings;
+use redlock::{Lock, RedLock};
+use std::sync::OnceLock;
+use tokio::{runtime::Handle, task};
+
+fn shared_redlock() -> &'static RedLock {
+    static REDLOCK: OnceLock<RedLock> = OnceLock::new();
+    return REDLOCK.get_or_init(|| RedLock::new(vec![app_settings().redis_url.as_str()]));
+}
+
+/**
+ * the held redis lock, released explicitly or when the guard was dropped
+ * the redlock client was blocking, the lock and the unlock ran off the runtime threads
+ */
+pub struct RedisLockGuard {
+    lock: Option<Lock<'static>>,
+}
+
+impl RedisLockGuard {
+    pub async fn release(&mut self) {
+        if let Some(lock) = self.lock.take() {
+            let _ = task::spawn_blocking(move || shared_redlock().unlock(&lock)).await;
+        }
+    }
+}
+
+impl Drop for RedisLockGuard {
+    fn drop(&mut self) {
+        let Some(lock) = self.lock.take() else {
+            return;
+        };
+        match Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || shared_redlock().unlock(&lock));
+            }
+            Err(_) => shared_redlock().unlock(&lock),
+        }
+    }
+}
+
+/**
+ * try to lock the resource once, None when another worker holds it
+ */
+pub async fn try_redis_lock(
+    resource: &str,
+    ttl_ms: usize,
+) -> Result<Option<RedisLockGuard>, String> {
+    let resource = resource.to_owned();
+    let locked = task::spawn_blocking(move || shared_redlock().lock(resource.as_bytes(), ttl_ms))
+        .await
+        .map_err(|e| format!("lock task failed: {}", e))?;
+    return locked
+        .map(|lock| lock.map(|l| RedisLockGuard { lock: Some(l) }))
+        .map_err(|e| e.to_string());
+}
@@ -0,0 +1,40 @@
+use crate::common::settings::app_settings::app_settings;
+use log::info;
+use std::{sync::OnceLock, time::Duration};
+
+/// wait for a free connection at most this long, the caller falls back as redis was down
+const CHECKOUT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub type RedisPool = r2d2::Pool<redis::Client>;
+pub type RedisCon = r2d2::PooledConnection<redis::Client>;
+
+/**
+ * the redis connections of the synchronous callers, the compile jobs use the async connections
+ * the broken connection was dropped by the pool and opened again
+ */
+pub fn redis_pool() -> &'static RedisPool {
+    static POOL: OnceLock<RedisPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let settings = app_settings();
+        // the url was validated at startup
+        let client = redis::Client::open(settings.redis_url.as_str()).unwrap();
+        let max_size = settings.cv.redis_pool_max_size.max(1);
+        info!("redis pool created, max size: {}", max_size);
+        // connect lazily, the process should start even redis was down
+        r2d2::Pool::builder()
+            .max_size(max_size)
+            .min_idle(Some(0))
+            .connection_timeout(CHECKOUT_TIMEOUT)
+            .test_on_check_out(true)
+            .build_unchecked(client)
+    })
+}
+
+/**
+ * check out a connection, it goes back to the pool when dropped
+ */
+pub fn redis_con() -> Result<RedisCon, String> {
+    return redis_pool()
+        .get()
+        .map_err(|e| format!("get redis connection failed: {}", e));
+}
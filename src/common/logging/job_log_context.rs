@@ -0,0 +1,98 @@
+use crate::model::project::compile_app_params::CompileAppParams;
+use log::info;
+use log_mdc::ExtendGuard;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+/// the mdc key of the compile stage, updated by the job state transition
+const STAGE_KEY: &str = "stage";
+
+/**
+ * the per-job fields attached to every log record of the job
+ * the json encoder writes them to the `mdc` object, the human pattern shows the qid
+ */
+pub struct JobLogContext {
+    fields: Vec<(String, String)>,
+}
+
+impl JobLogContext {
+    pub fn of(params: &CompileAppParams) -> Self {
+        return JobLogContext {
+            fields: vec![
+                ("project_id".to_owned(), params.project_id.clone()),
+                ("qid".to_owned(), params.qid.to_string()),
+                ("user_id".to_owned(), params.user_id.to_string()),
+                (STAGE_KEY.to_owned(), "queued".to_owned()),
+            ],
+        };
+    }
+
+    /**
+     * attach the fields to the log records of the current thread, until the guard was dropped
+     */
+    pub fn enter(&self) -> ExtendGuard {
+        return log_mdc::extend_scoped(self.fields.clone());
+    }
+
+    /**
+     * attach the fields to the log records of the future, the mdc was thread local
+     * so the fields were set on every poll and restored after it, the other tasks on the thread did not see them
+     */
+    pub fn scope<F: Future>(self, fut: F) -> JobLogFuture<F> {
+        return JobLogFuture {
+            context: self,
+            inner: Box::pin(fut),
+        };
+    }
+
+    /// keep the stage set by the job in this poll for the next poll
+    fn save_stage(&mut self) {
+        let stage = log_mdc::get(STAGE_KEY, |v| v.map(|s| s.to_owned()));
+        if let (Some(stage), Some(field)) = (
+            stage,
+            self.fields.iter_mut().find(|(key, _)| key == STAGE_KEY),
+        ) {
+            field.1 = stage;
+        }
+    }
+}
+
+pub struct JobLogFuture<F: Future> {
+    context: JobLogContext,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for JobLogFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let _guard = this.context.enter();
+        let poll = this.inner.as_mut().poll(cx);
+        this.context.save_stage();
+        return poll;
+    }
+}
+
+/**
+ * the current compile stage of the job, the job log context restore it after the job
+ */
+pub fn set_log_stage(stage: &str) {
+    log_mdc::insert(STAGE_KEY, stage);
+}
+
+/**
+ * log the finished stage with the duration, for the log based stage timing
+ */
+pub fn log_stage_finished(stage: &str, started: Instant) {
+    let duration_ms = started.elapsed().as_millis().to_string();
+    let _guard = log_mdc::extend_scoped(vec![
+        (STAGE_KEY.to_owned(), stage.to_owned()),
+        ("duration_ms".to_owned(), duration_ms.clone()),
+    ]);
+    info!("compile stage {} finished in {}ms", stage, duration_ms);
+}
@@ -0,0 +1 @@
+pub mod job_log_context;
\ No newline at end of file
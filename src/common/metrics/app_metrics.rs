@@ -0,0 +1,168 @@
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    Encoder, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGauge, TextEncoder,
+};
+use rust_wheel::texhub::proj::compile_result::CompileResult;
+use std::{
+    sync::OnceLock,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// the compile stages take seconds to minutes
+const STAGE_BUCKETS: &[f64] = &[
+    0.1, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+];
+/// the synctex query should answer in milliseconds
+const QUERY_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+pub struct AppMetrics {
+    /// stage: download, unzip, engine, upload, total
+    pub compile_stage_seconds: HistogramVec,
+    pub compile_total: IntCounterVec,
+    /// the time from the texhub request to the worker picked it up
+    pub compile_queue_lag_seconds: HistogramVec,
+    pub compile_in_flight: IntGauge,
+    pub compile_pending: IntGauge,
+    /// 1 while the compile stream consumer runs, 0 while it waits for the restart
+    pub compile_consumer_up: IntGauge,
+    pub compile_consumer_restarts: IntCounter,
+    pub cv_render_total: IntCounterVec,
+    pub synctex_query_seconds: HistogramVec,
+}
+
+impl AppMetrics {
+    fn register() -> Self {
+        return AppMetrics {
+            compile_stage_seconds: register_histogram_vec!(
+                "texhub_compile_stage_seconds",
+                "the duration of the project compile stages",
+                &["stage"],
+                STAGE_BUCKETS.to_vec()
+            )
+            .unwrap(),
+            compile_total: register_int_counter_vec!(
+                "texhub_compile_total",
+                "the project compiles by the result and the engine",
+                &["result", "engine"]
+            )
+            .unwrap(),
+            compile_queue_lag_seconds: register_histogram_vec!(
+                "texhub_compile_queue_lag_seconds",
+                "the time from the compile request to the worker picked it up",
+                &["priority"],
+                STAGE_BUCKETS.to_vec()
+            )
+            .unwrap(),
+            compile_in_flight: register_int_gauge!(
+                "texhub_compile_in_flight",
+                "the project compiles running in this worker"
+            )
+            .unwrap(),
+            compile_pending: register_int_gauge!(
+                "texhub_compile_pending",
+                "the project compiles waiting for the scheduler slot in this worker"
+            )
+            .unwrap(),
+            compile_consumer_up: register_int_gauge!(
+                "texhub_compile_consumer_up",
+                "the compile stream consumer of this worker was running"
+            )
+            .unwrap(),
+            compile_consumer_restarts: register_int_counter!(
+                "texhub_compile_consumer_restarts_total",
+                "the compile stream consumer restarts after the crash"
+            )
+            .unwrap(),
+            cv_render_total: register_int_counter_vec!(
+                "cv_render_total",
+                "the cv renders by the template code and the result",
+                &["template", "result"]
+            )
+            .unwrap(),
+            synctex_query_seconds: register_histogram_vec!(
+                "texhub_synctex_query_seconds",
+                "the synctex query latency",
+                &["query"],
+                QUERY_BUCKETS.to_vec()
+            )
+            .unwrap(),
+        };
+    }
+}
+
+pub fn app_metrics() -> &'static AppMetrics {
+    static METRICS: OnceLock<AppMetrics> = OnceLock::new();
+    METRICS.get_or_init(AppMetrics::register)
+}
+
+pub fn observe_compile_stage(stage: &str, started: Instant) {
+    app_metrics()
+        .compile_stage_seconds
+        .with_label_values(&[stage])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+pub fn observe_compile_result(result: &Option<CompileResult>, engine: &str) {
+    let result_name = match result {
+        Some(CompileResult::Success) => "success",
+        Some(CompileResult::Failure) => "failure",
+        _ => "unknown",
+    };
+    app_metrics()
+        .compile_total
+        .with_label_values(&[result_name, engine])
+        .inc();
+}
+
+/**
+ * the `req_time` was the unix epoch in milliseconds, the seconds was accepted too
+ */
+pub fn observe_compile_queue_lag(req_time: i64, priority: &str) {
+    let now_millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    let req_millis = if req_time < 100_000_000_000 {
+        req_time * 1000
+    } else {
+        req_time
+    };
+    if req_time <= 0 || now_millis < req_millis {
+        return;
+    }
+    app_metrics()
+        .compile_queue_lag_seconds
+        .with_label_values(&[priority])
+        .observe((now_millis - req_millis) as f64 / 1000.0);
+}
+
+pub fn observe_cv_render(template_code: &str, success: bool) {
+    let result_name = if success { "success" } else { "failure" };
+    app_metrics()
+        .cv_render_total
+        .with_label_values(&[template_code, result_name])
+        .inc();
+}
+
+/**
+ * the timer observes the duration when dropped, for the stage with many return paths
+ */
+pub fn synctex_query_timer(query: &str) -> HistogramTimer {
+    return app_metrics()
+        .synctex_query_seconds
+        .with_label_values(&[query])
+        .start_timer();
+}
+
+/**
+ * the prometheus text exposition of all the registered metrics
+ */
+pub fn gather_metrics_text() -> Result<String, String> {
+    // make sure the metrics were registered before the first compile
+    app_metrics();
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|e| format!("encode metrics failed: {}", e))?;
+    return String::from_utf8(buffer).map_err(|e| format!("metrics is not utf-8: {}", e));
+}
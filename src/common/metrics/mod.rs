@@ -0,0 +1 @@
+pub mod app_metrics;
\ No newline at end of file
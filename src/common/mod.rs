@@ -1 +1,7 @@
-pub mod interop;
\ No newline at end of file
+pub mod auth;
+pub mod cache;
+pub mod interop;
+pub mod logging;
+pub mod metrics;
+pub mod settings;
+pub mod trace;
\ No newline at end of file
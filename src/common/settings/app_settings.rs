@@ -0,0 +1,202 @@
+use super::settings_validation::validate_settings;
+use config::{Config, Environment, File};
+use serde::Deserialize;
+use std::{env, sync::OnceLock};
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/**
+ * the typed app settings, loaded once at startup
+ * settings.toml, then settings-production.toml when `ENV` is production, then the env overrides
+ * like `CV__TEXHUB_API_URL` and `SERVER__WORKERS`
+ */
+#[derive(Deserialize, Debug, Clone)]
+pub struct Settings {
+    #[serde(default)]
+    pub server: ServerSettings,
+    #[serde(default)]
+    pub cv: CvSettings,
+    /// read from `REDIS_URL`
+    #[serde(skip)]
+    pub redis_url: String,
+    /// read from `CV_REMOTE_SSH_PWD`, only the cv output sync needs it
+    #[serde(skip)]
+    pub cv_remote_ssh_pwd: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ServerSettings {
+    pub host: String,
+    pub port: u16,
+    pub workers: usize,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        return ServerSettings {
+            host: "0.0.0.0".to_owned(),
+            port: 8001,
+            workers: 3,
+        };
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CvSettings {
+    pub cv_compile_base_dir: String,
+    pub x_access_token: String,
+    pub cv_api_url: String,
+    pub texhub_api_url: String,
+    pub compile_group_name: String,
+    pub compile_stream_redis_key: String,
+    /// the shared nfs project dir
+    pub texhub_proj_base_dir: String,
+    pub texhub_proj_compile_base_dir: String,
+    pub preview_dpi: u32,
+    pub preview_thumb_width: u32,
+    pub preview_all_pages: bool,
+    pub preview_format: String,
+    pub sample_cv_path: String,
+    pub compile_timeout_secs: u64,
+    pub compile_max_concurrent_jobs: usize,
+    pub compile_max_user_concurrent_jobs: usize,
+    /// the jobs waiting in one worker, the consumer stops reading the stream when it was full
+    pub compile_max_pending_jobs: usize,
+    /// the waiting jobs of one user in one worker, the job beyond it was failed
+    pub compile_max_user_pending_jobs: usize,
+    pub user_config_cache_secs: u64,
+    /// the cron of the expired compile queue check, with the seconds field
+    pub expire_check_cron: String,
+    pub readiness_required_fonts: String,
+    pub readiness_min_free_mb: u64,
+    pub readiness_heartbeat_stale_secs: u64,
+    pub trace_exporter: String,
+    pub trace_otlp_endpoint: String,
+    pub trace_service_name: String,
+    pub log_format: String,
+    /// the running compiles could finish in this time on shutdown, keep it below the pod termination grace period
+    pub shutdown_drain_secs: u64,
+    /// the redis connections shared by the consumer, the log sink and the cancel signal
+    pub redis_pool_max_size: u32,
+    /// the live compile log viewers at the same time, each one holds a redis connection out of the pool
+    pub compile_log_max_subscribers: usize,
+    /// the compile stream consumer restarts after a crash, the wait doubles up to this
+    pub consumer_backoff_max_secs: u64,
+    /// the idempotent texhub calls were sent at most this many times
+    pub texhub_retry_max_attempts: u32,
+    /// the wait before the first retry, doubled for the next one
+    pub texhub_retry_backoff_millis: u64,
+    /// the texhub circuit opens after this many consecutive failures
+    pub texhub_breaker_failure_threshold: u32,
+    pub texhub_breaker_open_secs: u64,
+    /// none | hmac, the inbound requests out of the actuator should be signed in hmac mode
+    pub service_auth_mode: String,
+    /// the shared secret of the request signature, the outbound requests were signed when it was set
+    pub service_auth_secret: String,
+    /// the signed timestamp older or newer than this was rejected
+    pub service_auth_max_skew_secs: u64,
+}
+
+impl Default for CvSettings {
+    fn default() -> Self {
+        return CvSettings {
+            cv_compile_base_dir: "/opt/data/cv/output".to_owned(),
+            x_access_token: "".to_owned(),
+            cv_api_url: "".to_owned(),
+            texhub_api_url: "".to_owned(),
+            compile_group_name: "g-comp-queue".to_owned(),
+            compile_stream_redis_key: "texhub-server:proj:s-comp-queue".to_owned(),
+            texhub_proj_base_dir: "/opt/data/project".to_owned(),
+            texhub_proj_compile_base_dir: "/tmp/texhub-compile".to_owned(),
+            preview_dpi: 96,
+            preview_thumb_width: 600,
+            preview_all_pages: false,
+            preview_format: "png".to_owned(),
+            sample_cv_path: "./config/cv/sample-cv.json".to_owned(),
+            compile_timeout_secs: 300,
+            compile_max_concurrent_jobs: 4,
+            compile_max_user_concurrent_jobs: 2,
+            compile_max_pending_jobs: 8,
+            compile_max_user_pending_jobs: 4,
+            user_config_cache_secs: 60,
+            expire_check_cron: "1/45 * * * * *".to_owned(),
+            readiness_required_fonts: "".to_owned(),
+            readiness_min_free_mb: 1024,
+            readiness_heartbeat_stale_secs: 30,
+            trace_exporter: "none".to_owned(),
+            trace_otlp_endpoint: "http://127.0.0.1:4317".to_owned(),
+            trace_service_name: "cv-render".to_owned(),
+            log_format: "human".to_owned(),
+            shutdown_drain_secs: 20,
+            redis_pool_max_size: 32,
+            compile_log_max_subscribers: 256,
+            consumer_backoff_max_secs: 60,
+            texhub_retry_max_attempts: 3,
+            texhub_retry_backoff_millis: 200,
+            texhub_breaker_failure_threshold: 5,
+            texhub_breaker_open_secs: 30,
+            service_auth_mode: "none".to_owned(),
+            service_auth_secret: "".to_owned(),
+            service_auth_max_skew_secs: 300,
+        };
+    }
+}
+
+impl Settings {
+    fn load() -> Result<Self, String> {
+        let mut conf = Config::default();
+        conf.merge(File::with_name("settings").required(false))
+            .map_err(|e| format!("read settings.toml failed: {}", e))?;
+        let env_name = env::var("ENV").unwrap_or_default();
+        if env_name == "pro" || env_name == "production" {
+            conf.merge(File::with_name("settings-production").required(false))
+                .map_err(|e| format!("read settings-production.toml failed: {}", e))?;
+        }
+        conf.merge(Environment::new().separator("__"))
+            .map_err(|e| format!("read settings env overrides failed: {}", e))?;
+        let mut settings: Settings = conf
+            .try_into()
+            .map_err(|e| format!("parse settings failed: {}", e))?;
+        settings.redis_url = env::var("REDIS_URL").unwrap_or_default();
+        settings.cv_remote_ssh_pwd = env::var("CV_REMOTE_SSH_PWD").ok();
+        return Ok(settings);
+    }
+
+    /**
+     * the font families the templates need, `cv.readiness_required_fonts` was comma separated
+     */
+    pub fn required_fonts(&self) -> Vec<String> {
+        return self
+            .cv
+            .readiness_required_fonts
+            .split(',')
+            .map(|f| f.trim().to_owned())
+            .filter(|f| !f.is_empty())
+            .collect();
+    }
+}
+
+/**
+ * load and validate the settings, called once before the server and the workers start
+ * return every problem found, the process should not start with any of them
+ */
+pub fn init_settings() -> Result<&'static Settings, String> {
+    if let Some(s) = SETTINGS.get() {
+        return Ok(s);
+    }
+    let settings = Settings::load()?;
+    validate_settings(&settings)?;
+    return Ok(SETTINGS.get_or_init(|| settings));
+}
+
+/**
+ * the settings validated at startup
+ */
+pub fn app_settings() -> &'static Settings {
+    return SETTINGS.get_or_init(|| match Settings::load() {
+        Ok(s) => s,
+        Err(e) => panic!("settings used before init: {}", e),
+    });
+}
@@ -0,0 +1,118 @@
+use super::app_settings::Settings;
+use crate::util::fs_util::ensure_writable_dir;
+use tokio_cron_scheduler::Job;
+
+/**
+ * check the settings before the process start, all the problems were reported together
+ */
+pub fn validate_settings(settings: &Settings) -> Result<(), String> {
+    let mut errors: Vec<String> = Vec::new();
+    let cv = &settings.cv;
+    check_url(&mut errors, "cv.cv_api_url", &cv.cv_api_url);
+    check_url(&mut errors, "cv.texhub_api_url", &cv.texhub_api_url);
+    if settings.redis_url.is_empty() {
+        errors.push("REDIS_URL is missing".to_owned());
+    } else if let Err(e) = redis::Client::open(settings.redis_url.as_str()) {
+        errors.push(format!("REDIS_URL is invalid: {}", e));
+    }
+    for (key, dir) in [
+        ("cv.cv_compile_base_dir", &cv.cv_compile_base_dir),
+        (
+            "cv.texhub_proj_compile_base_dir",
+            &cv.texhub_proj_compile_base_dir,
+        ),
+    ] {
+        if let Err(e) = ensure_writable_dir(dir) {
+            errors.push(format!("{} {} {}", key, dir, e));
+        }
+    }
+    if cv.compile_stream_redis_key.is_empty() {
+        errors.push("cv.compile_stream_redis_key is missing".to_owned());
+    }
+    if let Err(e) = Job::new(cv.expire_check_cron.as_str(), |_uuid, _l| {}) {
+        errors.push(format!(
+            "cv.expire_check_cron {} is invalid: {}",
+            cv.expire_check_cron, e
+        ));
+    }
+    check_one_of(
+        &mut errors,
+        "cv.preview_format",
+        &cv.preview_format,
+        &["png", "webp"],
+    );
+    check_one_of(
+        &mut errors,
+        "cv.trace_exporter",
+        &cv.trace_exporter,
+        &["none", "stdout", "otlp"],
+    );
+    check_one_of(
+        &mut errors,
+        "cv.log_format",
+        &cv.log_format,
+        &["human", "json"],
+    );
+    check_one_of(
+        &mut errors,
+        "cv.service_auth_mode",
+        &cv.service_auth_mode,
+        &["none", "hmac"],
+    );
+    if cv.service_auth_mode == "hmac" && cv.service_auth_secret.is_empty() {
+        errors.push("cv.service_auth_secret is missing in the hmac mode".to_owned());
+    }
+    if cv.compile_timeout_secs == 0 {
+        errors.push("cv.compile_timeout_secs should be greater than 0".to_owned());
+    }
+    if cv.compile_max_concurrent_jobs == 0 || cv.compile_max_user_concurrent_jobs == 0 {
+        errors.push("cv.compile_max_concurrent_jobs and cv.compile_max_user_concurrent_jobs should be greater than 0".to_owned());
+    }
+    if cv.compile_max_pending_jobs == 0 || cv.compile_max_user_pending_jobs == 0 {
+        errors.push(
+            "cv.compile_max_pending_jobs and cv.compile_max_user_pending_jobs should be greater than 0"
+                .to_owned(),
+        );
+    }
+    if cv.redis_pool_max_size == 0 || cv.consumer_backoff_max_secs == 0 {
+        errors.push(
+            "cv.redis_pool_max_size and cv.consumer_backoff_max_secs should be greater than 0"
+                .to_owned(),
+        );
+    }
+    if cv.compile_log_max_subscribers == 0 {
+        errors.push("cv.compile_log_max_subscribers should be greater than 0".to_owned());
+    }
+    if cv.texhub_retry_max_attempts == 0 || cv.texhub_breaker_failure_threshold == 0 {
+        errors.push(
+            "cv.texhub_retry_max_attempts and cv.texhub_breaker_failure_threshold should be greater than 0"
+                .to_owned(),
+        );
+    }
+    if settings.server.workers == 0 {
+        errors.push("server.workers should be greater than 0".to_owned());
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    return Err(format!("invalid settings:\n  {}", errors.join("\n  ")));
+}
+
+fn check_url(errors: &mut Vec<String>, key: &str, url: &str) {
+    if url.is_empty() {
+        errors.push(format!("{} is missing", key));
+    } else if !url.starts_with("http://") && !url.starts_with("https://") {
+        errors.push(format!("{} {} is not a http url", key, url));
+    }
+}
+
+fn check_one_of(errors: &mut Vec<String>, key: &str, value: &str, allowed: &[&str]) {
+    if !allowed.contains(&value) {
+        errors.push(format!(
+            "{} {} should be one of {}",
+            key,
+            value,
+            allowed.join(", ")
+        ));
+    }
+}
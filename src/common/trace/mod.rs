@@ -0,0 +1,2 @@
+pub mod trace_init;
+pub mod trace_propagation;
\ No newline at end of file
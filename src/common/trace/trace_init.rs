@@ -0,0 +1,84 @@
+use crate::common::settings::app_settings::app_settings;
+use log::{error, info};
+use opentelemetry::{global, trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{self as sdktrace, Tracer, TracerProvider},
+    Resource,
+};
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+/**
+ * install the opentelemetry tracer, `cv.trace_exporter`: none | stdout | otlp
+ * the spans were dropped with none, the trace context was still propagated to texhub
+ */
+pub fn init_tracing() {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let exporter = app_settings().cv.trace_exporter.clone();
+    let tracer = match exporter.as_str() {
+        "otlp" => otlp_tracer(),
+        "stdout" => Some(stdout_tracer()),
+        _ => None,
+    };
+    let tracer = match tracer {
+        Some(t) => t,
+        None => {
+            info!("tracing exporter disabled, exporter: {}", exporter);
+            return;
+        }
+    };
+    let subscriber = Registry::default().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        error!("install tracing subscriber failed: {}", e);
+        return;
+    }
+    info!("tracing initialized, exporter: {}", exporter);
+}
+
+/**
+ * flush the pending spans before the process exit
+ */
+pub fn shutdown_tracing() {
+    global::shutdown_tracer_provider();
+}
+
+fn trace_config() -> sdktrace::Config {
+    let service_name = app_settings().cv.trace_service_name.clone();
+    return sdktrace::config().with_resource(Resource::new(vec![KeyValue::new(
+        "service.name",
+        service_name,
+    )]));
+}
+
+/// export to the local otlp collector by grpc, `cv.trace_otlp_endpoint`
+fn otlp_tracer() -> Option<Tracer> {
+    let endpoint = app_settings().cv.trace_otlp_endpoint.clone();
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint.clone()),
+        )
+        .with_trace_config(trace_config())
+        .install_batch(runtime::Tokio);
+    match tracer {
+        Ok(t) => Some(t),
+        Err(e) => {
+            error!("install otlp tracer failed: {}, endpoint: {}", e, endpoint);
+            None
+        }
+    }
+}
+
+fn stdout_tracer() -> Tracer {
+    let provider = TracerProvider::builder()
+        .with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+        .with_config(trace_config())
+        .build();
+    let tracer = provider.tracer("cv-render");
+    global::set_tracer_provider(provider);
+    return tracer;
+}
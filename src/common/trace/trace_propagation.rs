@@ -0,0 +1,60 @@
+use opentelemetry::{global, propagation::Injector, trace::TraceContextExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl<'a> Injector for HeaderInjector<'a> {
+    fn set(&mut self, key: &str, value: String) {
+        let name = HeaderName::from_bytes(key.as_bytes());
+        let value = HeaderValue::from_str(&value);
+        if let (Ok(n), Ok(v)) = (name, value) {
+            self.0.insert(n, v);
+        }
+    }
+}
+
+/**
+ * put the trace context of the current span to the outgoing request headers
+ * the x-request-id was the trace id, so the texhub log could be joined with the trace
+ */
+pub fn inject_trace_headers(headers: &mut HeaderMap) {
+    let cx = Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&cx, &mut HeaderInjector(headers));
+    });
+    let span_context = cx.span().span_context().clone();
+    let request_id = if span_context.is_valid() {
+        span_context.trace_id().to_string()
+    } else {
+        uuid::Uuid::new_v4().to_string()
+    };
+    if let Ok(v) = HeaderValue::from_str(&request_id) {
+        headers.insert("x-request-id", v);
+    }
+}
+
+/**
+ * the trace context of the current span, carried by the compile job to the worker thread
+ */
+pub fn capture_trace_context() -> HashMap<String, String> {
+    let mut carrier: HashMap<String, String> = HashMap::new();
+    let cx = Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&cx, &mut carrier);
+    });
+    return carrier;
+}
+
+/**
+ * continue the trace captured by `capture_trace_context` in the span
+ */
+pub fn attach_trace_context(span: &Span, carrier: &HashMap<String, String>) {
+    if carrier.is_empty() {
+        return;
+    }
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(carrier));
+    span.set_parent(parent);
+}
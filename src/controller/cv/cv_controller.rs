@@ -0,0 +1,112 @@
+use actix_web::{web, HttpResponse};
+use log::error;
+use rust_wheel::model::response::api_response::ApiResponse;
+use std::{fs, io::ErrorKind};
+
+use crate::{
+    common::settings::app_settings::Settings,
+    model::{
+        cv::cv_main::CvMainResp,
+        request::cv::cv_preview_request::{CvPreviewParams, CvPreviewRequest, TplPreviewRequest},
+    },
+    render::render_worker::{render_cv_preview, render_template_preview},
+};
+
+/**
+ * render the cv synchronously for the editor live preview
+ * return the pdf bytes, or the tex source with `?format=tex`
+ */
+pub async fn preview_cv(
+    params: web::Query<CvPreviewParams>,
+    form: web::Json<CvPreviewRequest>,
+) -> HttpResponse {
+    let tex_only = params.format.as_deref() == Some("tex");
+    let template_code = form.template_code.clone();
+    match render_cv_preview(form.into_inner(), tex_only).await {
+        Ok(bytes) => {
+            let content_type = if tex_only {
+                "application/x-tex; charset=utf-8"
+            } else {
+                "application/pdf"
+            };
+            return HttpResponse::Ok().content_type(content_type).body(bytes);
+        }
+        Err(e) => {
+            error!("preview cv failed: {}, template: {}", e, template_code);
+            let res = ApiResponse {
+                result: e.to_string(),
+                ..Default::default()
+            };
+            if e.kind() == ErrorKind::InvalidInput {
+                return HttpResponse::BadRequest().json(res);
+            }
+            return HttpResponse::InternalServerError().json(res);
+        }
+    }
+}
+
+/**
+ * regenerate the template gallery preview image
+ * return the preview image path on the server for the template `preview_url`
+ */
+pub async fn regenerate_tpl_preview(
+    settings: web::Data<Settings>,
+    form: web::Json<TplPreviewRequest>,
+) -> HttpResponse {
+    let req = form.into_inner();
+    let cv_main = match req.cv_main {
+        Some(cv) => cv,
+        None => match load_sample_cv(&settings.cv.sample_cv_path) {
+            Some(cv) => cv,
+            None => {
+                let res = ApiResponse {
+                    result: "load sample cv failed".to_owned(),
+                    ..Default::default()
+                };
+                return HttpResponse::InternalServerError().json(res);
+            }
+        },
+    };
+    let result = render_template_preview(req.template_code.clone(), cv_main).await;
+    if let Err(e) = &result {
+        error!(
+            "regenerate template preview failed: {}, template: {}",
+            e, req.template_code
+        );
+    }
+    let res = ApiResponse {
+        result: match &result {
+            Ok(preview) => preview.to_owned(),
+            Err(e) => e.to_string(),
+        },
+        ..Default::default()
+    };
+    return match result {
+        Ok(_) => HttpResponse::Ok().json(res),
+        Err(e) if e.kind() == ErrorKind::InvalidInput => HttpResponse::BadRequest().json(res),
+        Err(_) => HttpResponse::InternalServerError().json(res),
+    };
+}
+
+fn load_sample_cv(sample_path: &str) -> Option<CvMainResp> {
+    let content = fs::read_to_string(sample_path);
+    if let Err(e) = content {
+        error!("read sample cv failed: {}, path: {}", e, sample_path);
+        return None;
+    }
+    match serde_json::from_str::<CvMainResp>(&content.unwrap()) {
+        Ok(cv) => return Some(cv),
+        Err(e) => {
+            error!("parse sample cv failed: {}, path: {}", e, sample_path);
+            return None;
+        }
+    }
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/render/cv/v1")
+            .route("/preview", web::post().to(preview_cv))
+            .route("/tpl/preview", web::post().to(regenerate_tpl_preview)),
+    );
+}
@@ -0,0 +1 @@
+pub mod cv_controller;
\ No newline at end of file
@@ -1,3 +1,4 @@
 pub mod tex;
 pub mod proj;
-pub mod monitor;
\ No newline at end of file
+pub mod monitor;
+pub mod cv;
\ No newline at end of file
@@ -1,7 +1,22 @@
+use crate::{
+    common::settings::app_settings::Settings, service::readiness_service::check_readiness,
+};
 use actix_web::{web, HttpResponse, Responder};
+use log::error;
 
-pub async fn health() -> impl Responder  {
-    HttpResponse::Ok().body("Ok")
+/**
+ * the engines, fonts, redis, compile dirs and the stream consumer were checked
+ * return 503 with the breakdown when any of them was degraded
+ */
+pub async fn health(settings: web::Data<Settings>) -> impl Responder {
+    match web::block(move || check_readiness(&settings)).await {
+        Ok(resp) if resp.is_ready() => HttpResponse::Ok().json(resp),
+        Ok(resp) => HttpResponse::ServiceUnavailable().json(resp),
+        Err(e) => {
+            error!("readiness check failed: {}", e);
+            HttpResponse::ServiceUnavailable().body(e.to_string())
+        }
+    }
 }
 
 pub async fn liveness() -> impl Responder  {
@@ -12,5 +27,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/texhub/actuator")
             .route("/liveness", web::get().to(liveness))
+            .route("/readiness", web::get().to(health))
+            .route("/health", web::get().to(health))
     );
 }
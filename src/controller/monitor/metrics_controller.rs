@@ -0,0 +1,22 @@
+use crate::common::metrics::app_metrics::gather_metrics_text;
+use actix_web::{web, HttpResponse};
+use log::error;
+
+/**
+ * the prometheus scrape endpoint
+ */
+pub async fn metrics() -> HttpResponse {
+    match gather_metrics_text() {
+        Ok(text) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4; charset=utf-8")
+            .body(text),
+        Err(e) => {
+            error!("gather metrics failed: {}", e);
+            HttpResponse::InternalServerError().body(e)
+        }
+    }
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/metrics", web::get().to(metrics));
+}
@@ -1 +1,2 @@
-pub mod health_controller;
\ No newline at end of file
+pub mod health_controller;
+pub mod metrics_controller;
\ No newline at end of file
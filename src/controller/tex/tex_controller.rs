@@ -1,23 +1,46 @@
+use crate::common::auth::service_auth::{bind_job_owner, signed_user_id};
 use crate::model::project::compile_app_params::CompileAppParams;
-use crate::model::project::tex_file_compile_status::TeXFileCompileStatus;
-use crate::model::user::tex_user_config::TexUserConfig;
-use crate::render::render_worker::{
-    render_texhub_project, render_texhub_project_mq, render_texhub_project_sse,
+use crate::model::request::proj::compile_cancel_params::CompileCancelParams;
+use crate::model::request::proj::compile_log_params::CompileLogParams;
+use crate::model::response::tex::compile_log_event::CompileLogEvent;
+use crate::render::render_worker::{render_texhub_project, render_texhub_project_sse};
+use crate::service::compile_cancel_service::{
+    request_cancel_job, request_cancel_project, CancelOutcome,
 };
-use crate::render::texhub::pipeline::pipeline_nfs_render_works::render_texhub_project_pipeline_nfs;
-use crate::render::texhub::pipeline::pipeline_render_works::render_texhub_project_pipeline;
-use crate::rest::client::cv_client::{update_queue_status, update_queue_status_sync};
-use crate::rest::user::config::config_fetcher::get_one_user_config;
+use crate::service::compile_log_service::{acquire_log_subscriber, subscribe_compile_log};
 use actix_web::http::header::{CacheControl, CacheDirective};
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::Message;
+use futures::StreamExt;
 use log::{error, warn};
 use rust_wheel::common::util::net::sse_stream::SseStream;
 use rust_wheel::model::response::api_response::ApiResponse;
-use rust_wheel::texhub::proj::compile_result::CompileResult;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::{sync::mpsc::UnboundedReceiver, task};
 
-pub async fn compile_tex(params: web::Json<CompileAppParams>) -> impl Responder {
+/**
+ * the signed user acts only on the own compile job
+ */
+fn forbidden(req: &HttpRequest, reason: String) -> HttpResponse {
+    warn!("reject the request: {}, path: {}", reason, req.path());
+    let res = ApiResponse {
+        result: reason,
+        ..Default::default()
+    };
+    return HttpResponse::Forbidden().json(res);
+}
+
+/// the job owner was the signed user, the absent one was taken from the signature
+fn bind_params_owner(req: &HttpRequest, params: &mut CompileAppParams) -> Result<(), String> {
+    params.user_id = bind_job_owner(params.user_id, signed_user_id(req)?)?;
+    return Ok(());
+}
+
+pub async fn compile_tex(req: HttpRequest, params: web::Json<CompileAppParams>) -> HttpResponse {
+    let mut params = params.into_inner();
+    if let Err(e) = bind_params_owner(&req, &mut params) {
+        return forbidden(&req, e);
+    }
     let resp = render_texhub_project(&params).await;
     let res = ApiResponse {
         result: resp,
@@ -26,7 +49,14 @@ pub async fn compile_tex(params: web::Json<CompileAppParams>) -> impl Responder
     HttpResponse::Ok().json(res)
 }
 
-pub async fn compile_tex_sse(params: web::Query<CompileAppParams>) -> HttpResponse {
+pub async fn compile_tex_sse(
+    req: HttpRequest,
+    params: web::Query<CompileAppParams>,
+) -> HttpResponse {
+    let mut params = params.into_inner();
+    if let Err(e) = bind_params_owner(&req, &mut params) {
+        return forbidden(&req, e);
+    }
     let (tx, rx): (UnboundedSender<String>, UnboundedReceiver<String>) =
         tokio::sync::mpsc::unbounded_channel();
     task::spawn(async move {
@@ -42,61 +72,156 @@ pub async fn compile_tex_sse(params: web::Query<CompileAppParams>) -> HttpRespon
     response
 }
 
-pub async fn compile_tex_from_mq(params: CompileAppParams) {
-    task::spawn_blocking(move || {
-        let compile_result = render_texhub_project_pipeline(&params);
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        rt.block_on(update_queue_compile_result(params, compile_result));
-    });
+/// the viewers beyond `cv.compile_log_max_subscribers` retry later
+fn too_many_subscribers() -> HttpResponse {
+    let res = ApiResponse {
+        result: "too many compile log subscribers".to_owned(),
+        ..Default::default()
+    };
+    return HttpResponse::ServiceUnavailable().json(res);
 }
 
-pub async fn update_queue_compile_result(
-    params_arc: CompileAppParams,
-    compile_result: Option<CompileResult>,
-) {
-    if compile_result.is_none() {
-        warn!("compile result is none, params:{:?}", params_arc);
-    }
-    let u_result = update_queue_status(
-        TeXFileCompileStatus::Compiled as i32,
-        &params_arc.qid,
-        Some(compile_result.unwrap() as i32),
-    )
-    .await;
-    if !u_result {
-        error!("Failed to update result status, params: {:?}", &params_arc)
+/**
+ * the live compile log over sse, resume from the `last_id` or the `Last-Event-ID` header
+ */
+pub async fn compile_log_sse(
+    req: HttpRequest,
+    params: web::Query<CompileLogParams>,
+) -> HttpResponse {
+    let slot = match acquire_log_subscriber() {
+        Some(s) => s,
+        None => return too_many_subscribers(),
+    };
+    let mut log_params = params.into_inner();
+    if log_params.last_id.is_none() {
+        log_params.last_id = req
+            .headers()
+            .get("Last-Event-ID")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_owned());
     }
+    let (tx, rx): (
+        UnboundedSender<CompileLogEvent>,
+        UnboundedReceiver<CompileLogEvent>,
+    ) = tokio::sync::mpsc::unbounded_channel();
+    task::spawn(subscribe_compile_log(log_params, tx, slot));
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        let event = rx.recv().await?;
+        let id_line = match event.stream_id() {
+            Some(id) => format!("id: {}\n", id),
+            None => "".to_owned(),
+        };
+        let chunk = format!(
+            "{}event: {}\ndata: {}\n\n",
+            id_line,
+            event.event_name(),
+            serde_json::to_string(&event).unwrap()
+        );
+        Some((
+            Ok::<web::Bytes, actix_web::Error>(web::Bytes::from(chunk)),
+            rx,
+        ))
+    });
+    HttpResponse::Ok()
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .content_type("text/event-stream")
+        .streaming(stream)
 }
 
-pub fn update_queue_compile_result_sync(
-    params_arc: CompileAppParams,
-    compile_result: Option<CompileResult>,
-) {
-    if compile_result.is_none() {
-        warn!("compile result is none, params:{:?}", params_arc);
-    }
-    let u_result = update_queue_status_sync(
-        TeXFileCompileStatus::Compiled as i32,
-        &params_arc.qid,
-        Some(compile_result.unwrap() as i32),
-    );
-    if !u_result {
-        error!("Failed to update result status, params: {:?}", &params_arc)
-    }
+/**
+ * the live compile log over websocket, each text frame is a json `CompileLogEvent`
+ */
+pub async fn compile_log_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    params: web::Query<CompileLogParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let slot = match acquire_log_subscriber() {
+        Some(s) => s,
+        None => return Ok(too_many_subscribers()),
+    };
+    let (response, mut session, mut msg_stream) = actix_ws::handle(&req, body)?;
+    let (tx, mut rx): (
+        UnboundedSender<CompileLogEvent>,
+        UnboundedReceiver<CompileLogEvent>,
+    ) = tokio::sync::mpsc::unbounded_channel();
+    let log_params = params.into_inner();
+    task::spawn(subscribe_compile_log(log_params, tx, slot));
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                event = rx.recv() => match event {
+                    Some(ev) => {
+                        let terminal = ev.is_terminal();
+                        let text = serde_json::to_string(&ev).unwrap();
+                        if session.text(text).await.is_err() || terminal {
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+                msg = msg_stream.next() => match msg {
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    _ => {}
+                },
+            }
+        }
+        // close the receiver to stop the stream subscriber
+        drop(rx);
+        let _ = session.close(None).await;
+    });
+    Ok(response)
 }
 
-pub async fn update_queue_compile_status(
-    params_arc: CompileAppParams,
-    compile_status: TeXFileCompileStatus,
-) {
-    let u_result = update_queue_status(
-        compile_status as i32,
-        &params_arc.qid,
-        Some(CompileResult::Unknown as i32),
-    )
-    .await;
-    if !u_result {
-        error!("Failed to update queue status, params: {:?}", &params_arc)
+/**
+ * cancel the queued or running compile job by the qid, or the latest job of the project
+ * return the cancelled qid, the job state changes to cancelled once the worker sees the signal
+ * the signed user could only cancel the own job
+ */
+pub async fn cancel_compile(
+    req: HttpRequest,
+    params: web::Json<CompileCancelParams>,
+) -> HttpResponse {
+    let acting_user = match signed_user_id(&req) {
+        Ok(u) => u,
+        Err(e) => return forbidden(&req, e),
+    };
+    let cancel_params = params.into_inner();
+    let result = match (cancel_params.qid, cancel_params.project_id.as_deref()) {
+        (Some(qid), _) => request_cancel_job(qid, acting_user).await,
+        (None, Some(project_id)) => request_cancel_project(project_id, acting_user).await,
+        (None, None) => {
+            let res = ApiResponse {
+                result: "qid or project_id is required".to_owned(),
+                ..Default::default()
+            };
+            return HttpResponse::BadRequest().json(res);
+        }
+    };
+    match result {
+        Ok(CancelOutcome::Requested(qid)) => {
+            let res = ApiResponse {
+                result: qid,
+                ..Default::default()
+            };
+            HttpResponse::Ok().json(res)
+        }
+        Ok(CancelOutcome::NotOwner) => {
+            forbidden(&req, "the compile job was not owned by the user".to_owned())
+        }
+        Err(e) => {
+            error!("cancel compile failed: {}, params: {:?}", e, cancel_params);
+            let res = ApiResponse {
+                result: "cancel compile failed".to_owned(),
+                ..Default::default()
+            };
+            HttpResponse::InternalServerError().json(res)
+        }
     }
 }
 
@@ -104,6 +229,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/render/compile/v1")
             .route("/project", web::post().to(compile_tex))
-            .route("/project/sse", web::get().to(compile_tex_sse)),
+            .route("/project/sse", web::get().to(compile_tex_sse))
+            .route("/log/stream", web::get().to(compile_log_sse))
+            .route("/log/ws", web::get().to(compile_log_ws))
+            .route("/cancel", web::post().to(cancel_compile)),
     );
 }
@@ -1,12 +1,19 @@
+use actix_web::web;
 use actix_web::App;
 use actix_web::HttpServer;
 use actix_web_lab::__reexports::tracing::info;
+use actix_web_lab::middleware::from_fn;
 use controller::tex::tex_controller;
 use log::error;
 use task::app_init::initial_task;
-use task::compile_task_consumer::consume_redis_stream;
+use task::app_shutdown::{graceful_shutdown, wait_for_shutdown_signal};
 
+use crate::common::auth::service_auth::verify_service_auth;
+use crate::common::settings::app_settings::{init_settings, Settings};
+use crate::common::trace::trace_init::shutdown_tracing;
+use crate::controller::cv::cv_controller;
 use crate::controller::monitor::health_controller;
+use crate::controller::monitor::metrics_controller;
 use crate::controller::proj::proj_controller;
 
 mod common;
@@ -18,32 +25,54 @@ mod service;
 mod task;
 mod util;
 
-#[actix_web::main]
+/// the multi-thread runtime, the compile jobs run on it beside the http server
+#[tokio::main]
 async fn main() {
+    // the logging was not ready yet, report to stderr
+    let settings = match init_settings() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("load settings failed, {}", e);
+            std::process::exit(1);
+        }
+    };
     if let Err(e) = initial_task().await {
         error!("initial task failed, {}", e);
         return;
     }
-    let result = actix_main().await;
+    let result = actix_main(settings).await;
     match result {
         Ok(_) => {
-            info!("start the server success")
+            info!("the server stopped")
         }
         Err(e) => {
             error!("start the actix failed,{}", e)
         }
     }
+    shutdown_tracing();
 }
 
-async fn actix_main() -> std::io::Result<()> {
-    HttpServer::new(|| {
+async fn actix_main(settings: &'static Settings) -> std::io::Result<()> {
+    let settings_data = web::Data::new(settings.clone());
+    let server = HttpServer::new(move || {
         App::new()
+            .app_data(settings_data.clone())
+            .wrap(from_fn(verify_service_auth))
             .configure(tex_controller::config)
             .configure(health_controller::config)
+            .configure(metrics_controller::config)
             .configure(proj_controller::config)
+            .configure(cv_controller::config)
     })
-    .bind(("0.0.0.0", 8001))?
-    .workers(3)
-    .run()
-    .await
+    .bind((settings.server.host.as_str(), settings.server.port))?
+    .workers(settings.server.workers)
+    // the shutdown was coordinated with the compile drain below
+    .disable_signals()
+    .run();
+    let handle = server.handle();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        graceful_shutdown(handle).await;
+    });
+    server.await
 }
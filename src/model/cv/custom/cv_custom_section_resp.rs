@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct CvCustomSectionResp {
+    pub id: i64,
+    pub created_time: i64,
+    pub updated_time: i64,
+    pub cv_id: i64,
+    pub user_id: i64,
+    /// the id referenced by `CvMainResp.item_order`
+    pub item_id: i32,
+    pub title: String,
+    pub content: Option<String>,
+}
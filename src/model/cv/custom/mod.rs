@@ -0,0 +1 @@
+pub mod cv_custom_section_resp;
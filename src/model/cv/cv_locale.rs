@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/**
+ * the output language of the generated cv
+ * the bilingual cv show the chinese and english label side by side
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CvLocale {
+    #[default]
+    Zh,
+    En,
+    Bilingual,
+}
+
+impl From<Option<&str>> for CvLocale {
+    fn from(locale: Option<&str>) -> Self {
+        match locale.unwrap_or_default().trim().to_lowercase().as_str() {
+            "en" | "en-us" | "en_us" | "english" => CvLocale::En,
+            "bilingual" | "zh-en" | "zh_en" => CvLocale::Bilingual,
+            _ => CvLocale::Zh,
+        }
+    }
+}
+
+impl CvLocale {
+    /// the english only cv do not need the CJK font support
+    pub fn need_cjk(&self) -> bool {
+        *self != CvLocale::En
+    }
+}
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 
 use super::{
-    edu::edu::CvEduResp, project::cv_project_resp::CvProjectResp,
-    skill::cv_skill_resp::CvSkillResp, work::cv_work_resp::CvWorkResp, lang::cv_lang_resp::CvLangResp,
+    custom::cv_custom_section_resp::CvCustomSectionResp, edu::edu::CvEduResp,
+    lang::cv_lang_resp::CvLangResp, project::cv_project_resp::CvProjectResp,
+    skill::cv_skill_resp::CvSkillResp, work::cv_work_resp::CvWorkResp,
 };
 
 #[derive(Serialize, Deserialize, Default, Clone)]
@@ -15,19 +16,33 @@ pub struct CvMainResp {
     pub cv_status: i32,
     pub template_id: i64,
     pub employee_name: Option<String>,
+    /// the explicit name parts, fallback to split the `employee_name` when absent
+    pub family_name: Option<String>,
+    pub given_name: Option<String>,
     pub birthday: Option<String>,
     pub phone: Option<String>,
     pub email: Option<String>,
     pub stackoverflow: Option<String>,
     pub github: Option<String>,
     pub blog: Option<String>,
+    pub linkedin: Option<String>,
+    pub homepage: Option<String>,
+    pub location: Option<String>,
+    /// the profile photo url, downloaded to the compile dir before render
+    pub photo: Option<String>,
     pub item_order: String,
+    /// comma separated item ids that should not be rendered
+    pub hidden_items: Option<String>,
+    pub summary: Option<String>,
     pub main_color: Option<String>,
     pub theme: Option<String>,
     pub font_size: Option<String>,
+    /// the cv output language, zh(default), en or bilingual
+    pub locale: Option<String>,
     pub edu: Option<Vec<CvEduResp>>,
     pub work: Option<Vec<CvWorkResp>>,
     pub skills: Option<Vec<CvSkillResp>>,
     pub projects: Option<Vec<CvProjectResp>>,
     pub langs: Option<Vec<CvLangResp>>,
+    pub custom_sections: Option<Vec<CvCustomSectionResp>>,
 }
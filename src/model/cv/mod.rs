@@ -1,7 +1,9 @@
 pub mod cv_gen;
 pub mod cv_main;
+pub mod cv_locale;
 pub mod edu;
 pub mod work;
 pub mod skill;
 pub mod project;
-pub mod lang;
\ No newline at end of file
+pub mod lang;
+pub mod custom;
\ No newline at end of file
@@ -1,3 +1,5 @@
+use super::{compile_priority::CompilePriority, compile_settings::CompileSettings};
+use std::collections::HashMap;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct CompileAppParams {
@@ -9,4 +11,17 @@ pub struct CompileAppParams {
     pub version_no: String,
     pub log_file_name: String,
     pub proj_created_time: i64,
+    /// build in a per-job dir without waiting for the project lock, for the parallel version builds
+    #[serde(default)]
+    pub isolated: bool,
+    /// the owner of the job for the fair share scheduling, 0 when unknown
+    #[serde(default)]
+    pub user_id: i64,
+    #[serde(default)]
+    pub priority: CompilePriority,
+    #[serde(default)]
+    pub settings: CompileSettings,
+    /// the w3c trace context of the consumer span, the compile span continue the same trace
+    #[serde(default)]
+    pub trace_context: HashMap<String, String>,
 }
\ No newline at end of file
@@ -0,0 +1,213 @@
+use rust_wheel::texhub::proj::compile_result::CompileResult;
+use serde::{Deserialize, Serialize};
+
+use super::tex_file_compile_status::TeXFileCompileStatus;
+
+/**
+ * the lifecycle of one project compile job
+ * reported to texhub as the coarse `TeXFileCompileStatus` and `CompileResult` plus the detail name
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum CompileJobState {
+    Queued,
+    Downloading,
+    Compiling { pass: u32 },
+    PostProcessing,
+    Uploading,
+    Succeeded,
+    SucceededWithWarnings,
+    FailedWithErrors,
+    TimedOut,
+    Cancelled,
+}
+
+impl CompileJobState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompileJobState::Queued => "queued",
+            CompileJobState::Downloading => "downloading",
+            CompileJobState::Compiling { .. } => "compiling",
+            CompileJobState::PostProcessing => "post_processing",
+            CompileJobState::Uploading => "uploading",
+            CompileJobState::Succeeded => "succeeded",
+            CompileJobState::SucceededWithWarnings => "succeeded_with_warnings",
+            CompileJobState::FailedWithErrors => "failed_with_errors",
+            CompileJobState::TimedOut => "timed_out",
+            CompileJobState::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            CompileJobState::Succeeded
+                | CompileJobState::SucceededWithWarnings
+                | CompileJobState::FailedWithErrors
+                | CompileJobState::TimedOut
+                | CompileJobState::Cancelled
+        )
+    }
+
+    /**
+     * the job could be failed, timed out or cancelled from any running state
+     * otherwise it moves forward step by step, the compile pass only increase by one
+     */
+    pub fn can_transition_to(&self, next: &CompileJobState) -> bool {
+        if self.is_terminal() {
+            return false;
+        }
+        if matches!(
+            next,
+            CompileJobState::FailedWithErrors
+                | CompileJobState::TimedOut
+                | CompileJobState::Cancelled
+        ) {
+            return true;
+        }
+        match (self, next) {
+            (CompileJobState::Queued, CompileJobState::Downloading) => true,
+            (CompileJobState::Queued, CompileJobState::Compiling { pass }) => *pass == 1,
+            (CompileJobState::Downloading, CompileJobState::Compiling { pass }) => *pass == 1,
+            (
+                CompileJobState::Compiling { pass },
+                CompileJobState::Compiling { pass: next_pass },
+            ) => *next_pass == pass + 1,
+            (CompileJobState::Compiling { .. }, CompileJobState::PostProcessing) => true,
+            (CompileJobState::PostProcessing, CompileJobState::Uploading) => true,
+            (CompileJobState::Uploading, CompileJobState::Succeeded)
+            | (CompileJobState::Uploading, CompileJobState::SucceededWithWarnings) => true,
+            _ => false,
+        }
+    }
+
+    pub fn compile_status(&self) -> TeXFileCompileStatus {
+        match self {
+            CompileJobState::Queued => TeXFileCompileStatus::Waiting,
+            _ if self.is_terminal() => TeXFileCompileStatus::Compiled,
+            _ => TeXFileCompileStatus::Compiling,
+        }
+    }
+
+    pub fn compile_result(&self) -> CompileResult {
+        match self {
+            CompileJobState::Succeeded | CompileJobState::SucceededWithWarnings => {
+                CompileResult::Success
+            }
+            CompileJobState::FailedWithErrors
+            | CompileJobState::TimedOut
+            | CompileJobState::Cancelled => CompileResult::Failure,
+            _ => CompileResult::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CompileJobState::*;
+
+    const TERMINAL_STATES: [CompileJobState; 5] = [
+        Succeeded,
+        SucceededWithWarnings,
+        FailedWithErrors,
+        TimedOut,
+        Cancelled,
+    ];
+
+    const RUNNING_STATES: [CompileJobState; 5] = [
+        Queued,
+        Downloading,
+        Compiling { pass: 1 },
+        PostProcessing,
+        Uploading,
+    ];
+
+    #[test]
+    fn allowed_transitions() {
+        let cases = [
+            (Queued, Downloading),
+            (Queued, Compiling { pass: 1 }),
+            (Downloading, Compiling { pass: 1 }),
+            (Compiling { pass: 1 }, Compiling { pass: 2 }),
+            (Compiling { pass: 2 }, Compiling { pass: 3 }),
+            (Compiling { pass: 1 }, PostProcessing),
+            (PostProcessing, Uploading),
+            (Uploading, Succeeded),
+            (Uploading, SucceededWithWarnings),
+        ];
+        for (from, to) in cases {
+            assert!(from.can_transition_to(&to), "{:?} -> {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn rejected_transitions() {
+        let cases = [
+            (Queued, Queued),
+            (Queued, Compiling { pass: 2 }),
+            (Queued, PostProcessing),
+            (Queued, Succeeded),
+            (Downloading, Downloading),
+            (Downloading, Compiling { pass: 0 }),
+            (Downloading, Uploading),
+            (Compiling { pass: 1 }, Compiling { pass: 1 }),
+            (Compiling { pass: 1 }, Compiling { pass: 3 }),
+            (Compiling { pass: 2 }, Compiling { pass: 1 }),
+            (Compiling { pass: 1 }, Downloading),
+            (Compiling { pass: 1 }, Succeeded),
+            (PostProcessing, Compiling { pass: 2 }),
+            (PostProcessing, Succeeded),
+            (Uploading, PostProcessing),
+        ];
+        for (from, to) in cases {
+            assert!(!from.can_transition_to(&to), "{:?} -> {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn running_states_could_fail_time_out_or_cancel() {
+        for from in RUNNING_STATES {
+            for to in [FailedWithErrors, TimedOut, Cancelled] {
+                assert!(from.can_transition_to(&to), "{:?} -> {:?}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn terminal_states_were_immutable() {
+        for from in TERMINAL_STATES {
+            assert!(from.is_terminal());
+            for to in RUNNING_STATES.iter().chain(TERMINAL_STATES.iter()) {
+                assert!(!from.can_transition_to(to), "{:?} -> {:?}", from, to);
+            }
+        }
+        for state in RUNNING_STATES {
+            assert!(!state.is_terminal(), "{:?}", state);
+        }
+    }
+
+    #[test]
+    fn state_maps_to_the_compile_status_and_result() {
+        assert_eq!(Queued.compile_status(), TeXFileCompileStatus::Waiting);
+        for state in [
+            Downloading,
+            Compiling { pass: 2 },
+            PostProcessing,
+            Uploading,
+        ] {
+            assert_eq!(state.compile_status(), TeXFileCompileStatus::Compiling);
+            assert!(matches!(state.compile_result(), CompileResult::Unknown));
+        }
+        for state in TERMINAL_STATES {
+            assert_eq!(state.compile_status(), TeXFileCompileStatus::Compiled);
+        }
+        for state in [Succeeded, SucceededWithWarnings] {
+            assert!(matches!(state.compile_result(), CompileResult::Success));
+        }
+        for state in [FailedWithErrors, TimedOut, Cancelled] {
+            assert!(matches!(state.compile_result(), CompileResult::Failure));
+        }
+        assert!(matches!(Queued.compile_result(), CompileResult::Unknown));
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/**
+ * the scheduling priority of the compile job, the smaller rank was dispatched first
+ */
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CompilePriority {
+    /// the user is waiting in the editor
+    #[default]
+    Interactive = 0,
+    Cv = 1,
+    Batch = 2,
+}
+
+impl CompilePriority {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "batch" => CompilePriority::Batch,
+            "cv" => CompilePriority::Cv,
+            _ => CompilePriority::Interactive,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompilePriority::Interactive => "interactive",
+            CompilePriority::Cv => "cv",
+            CompilePriority::Batch => "batch",
+        }
+    }
+
+    pub fn all() -> [CompilePriority; 3] {
+        return [
+            CompilePriority::Interactive,
+            CompilePriority::Cv,
+            CompilePriority::Batch,
+        ];
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+/// the engines the user could choose, the others fall back to xelatex
+pub const SUPPORTED_ENGINES: [&str; 3] = ["xelatex", "pdflatex", "lualatex"];
+
+/**
+ * how the project source arrive at the compile dir
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CompileMode {
+    /// download the project zip from texhub
+    #[default]
+    Pipeline,
+    /// copy the project from the shared nfs dir
+    Nfs,
+    /// compile in the shared nfs project dir
+    InPlace,
+}
+
+impl CompileMode {
+    pub fn from_name(name: &str) -> Self {
+        match name.trim().to_lowercase().as_str() {
+            "nfs" => CompileMode::Nfs,
+            "in_place" | "inplace" => CompileMode::InPlace,
+            _ => CompileMode::Pipeline,
+        }
+    }
+}
+
+/**
+ * the per-user compile settings, resolved from the user config before the job was queued
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompileSettings {
+    pub mode: CompileMode,
+    pub engine: String,
+    /// typeset the images as boxes and skip the rerun passes, for the fast preview
+    pub draft: bool,
+    /// 0 means the server compile deadline
+    pub timeout_secs: u64,
+}
+
+impl Default for CompileSettings {
+    fn default() -> Self {
+        return CompileSettings {
+            mode: CompileMode::Pipeline,
+            engine: "xelatex".to_owned(),
+            draft: false,
+            timeout_secs: 0,
+        };
+    }
+}
+
+impl CompileSettings {
+    pub fn engine_name(&self) -> &str {
+        if SUPPORTED_ENGINES.contains(&self.engine.as_str()) {
+            return self.engine.as_str();
+        }
+        return "xelatex";
+    }
+
+    /**
+     * the engine arguments after the common options, the draft mode pass the option before the input
+     */
+    pub fn engine_input_args(&self, tex_file: &str) -> Vec<String> {
+        if !self.draft {
+            return vec![tex_file.to_owned()];
+        }
+        let jobname = std::path::Path::new(tex_file)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        return vec![
+            format!("-jobname={}", jobname),
+            format!(
+                "{}{}{}",
+                "\\PassOptionsToPackage{draft}{graphicx}\\input{", tex_file, "}"
+            ),
+        ];
+    }
+}
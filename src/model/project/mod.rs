@@ -1,3 +1,6 @@
 pub mod tex_comp_queue;
 pub mod tex_file_compile_status;
-pub mod compile_app_params;
\ No newline at end of file
+pub mod compile_app_params;
+pub mod compile_job_state;
+pub mod compile_priority;
+pub mod compile_settings;
\ No newline at end of file
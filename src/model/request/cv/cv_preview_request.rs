@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::cv::cv_main::CvMainResp;
+
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct CvPreviewRequest {
+    pub template_code: String,
+    pub cv_main: CvMainResp,
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct TplPreviewRequest {
+    pub template_code: String,
+    /// use the sample cv(`cv.sample_cv_path`) when absent
+    pub cv_main: Option<CvMainResp>,
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct CvPreviewParams {
+    /// pdf(default) or tex
+    pub format: Option<String>,
+}
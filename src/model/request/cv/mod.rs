@@ -1 +1,2 @@
-pub mod render_handle_request;
\ No newline at end of file
+pub mod render_handle_request;
+pub mod cv_preview_request;
\ No newline at end of file
@@ -1,12 +1,18 @@
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::model::cv::cv_main::CvMainResp;
+use crate::model::cv::{cv_locale::CvLocale, cv_main::CvMainResp};
 
 #[derive(Deserialize, Serialize)]
 #[allow(non_snake_case)]
 pub struct RenderHandleRequest<'r> {
     pub template_code: String,
     pub file_path: &'r str,
-    pub cv_main: CvMainResp
+    pub cv_main: CvMainResp,
+    /// the output language of the cv headings and labels
+    #[serde(default)]
+    pub locale: CvLocale,
+    /// the local path of the downloaded profile photo
+    #[serde(default)]
+    pub photo_path: Option<String>,
 }
\ No newline at end of file
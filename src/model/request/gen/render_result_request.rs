@@ -9,4 +9,7 @@ pub struct RenderResultRequest {
     pub id: i64,
     pub path: String,
     pub tex_file_path: String,
+    /// the first page preview image of the cv
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview_path: Option<String>,
 }
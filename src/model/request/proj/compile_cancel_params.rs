@@ -0,0 +1,7 @@
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct CompileCancelParams {
+    /// cancel the compile job by the queue id
+    pub qid: Option<i64>,
+    /// cancel the latest compile job of the project when the qid is absent
+    pub project_id: Option<String>,
+}
@@ -0,0 +1,7 @@
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct CompileLogParams {
+    pub project_id: String,
+    pub qid: i64,
+    /// resume after this redis stream entry id, read from the beginning when absent
+    pub last_id: Option<String>,
+}
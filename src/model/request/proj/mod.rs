@@ -1,3 +1,5 @@
 pub mod tex_proj_request;
 pub mod get_pdf_pos_params;
-pub mod get_src_pos_params;
\ No newline at end of file
+pub mod get_src_pos_params;
+pub mod compile_log_params;
+pub mod compile_cancel_params;
\ No newline at end of file
@@ -9,4 +9,7 @@ pub struct TexProjRequest {
     /// 渲染记录ID
     pub id: i64,
     pub comp_result: i32,
+    /// the detail compile job state, e.g. compiling, succeeded_with_warnings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_state: Option<String>,
 }
\ No newline at end of file
@@ -1,2 +1,3 @@
 pub mod tex;
-pub mod proj;
\ No newline at end of file
+pub mod proj;
+pub mod monitor;
\ No newline at end of file
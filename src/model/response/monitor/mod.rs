@@ -0,0 +1 @@
+pub mod readiness_resp;
\ No newline at end of file
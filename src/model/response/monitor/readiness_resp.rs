@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReadinessCheck {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/**
+ * status: ok when every check passed, otherwise degraded
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReadinessResp {
+    pub status: String,
+    pub checks: Vec<ReadinessCheck>,
+}
+
+impl ReadinessCheck {
+    pub fn pass(name: &str, detail: String) -> Self {
+        return ReadinessCheck {
+            name: name.to_owned(),
+            ok: true,
+            detail: detail,
+        };
+    }
+
+    pub fn fail(name: &str, detail: String) -> Self {
+        return ReadinessCheck {
+            name: name.to_owned(),
+            ok: false,
+            detail: detail,
+        };
+    }
+}
+
+impl ReadinessResp {
+    pub fn from_checks(checks: Vec<ReadinessCheck>) -> Self {
+        let status = if checks.iter().all(|c| c.ok) {
+            "ok"
+        } else {
+            "degraded"
+        };
+        return ReadinessResp {
+            status: status.to_owned(),
+            checks: checks,
+        };
+    }
+
+    pub fn is_ready(&self) -> bool {
+        return self.checks.iter().all(|c| c.ok);
+    }
+}
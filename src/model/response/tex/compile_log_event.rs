@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+/**
+ * the typed compile log event pushed to the websocket and sse client
+ * `id` is the redis stream entry id, the client resume from it after reconnect
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CompileLogEvent {
+    Log {
+        id: String,
+        line: String,
+    },
+    Diagnostic {
+        id: String,
+        level: String,
+        line: String,
+    },
+    /// status: the compile job state name, pass is present when compiling
+    Status {
+        id: String,
+        status: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pass: Option<u32>,
+    },
+    /// result: success, failure, requeued by the worker shutdown or unknown for the legacy end marker
+    End {
+        id: String,
+        result: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl CompileLogEvent {
+    pub fn stream_id(&self) -> Option<&str> {
+        match self {
+            CompileLogEvent::Log { id, .. }
+            | CompileLogEvent::Diagnostic { id, .. }
+            | CompileLogEvent::Status { id, .. }
+            | CompileLogEvent::End { id, .. } => Some(id),
+            CompileLogEvent::Error { .. } => None,
+        }
+    }
+
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            CompileLogEvent::Log { .. } => "log",
+            CompileLogEvent::Diagnostic { .. } => "diagnostic",
+            CompileLogEvent::Status { .. } => "status",
+            CompileLogEvent::End { .. } => "end",
+            CompileLogEvent::Error { .. } => "error",
+        }
+    }
+
+    /// no more event would be sent after the terminal event
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            CompileLogEvent::End { .. } | CompileLogEvent::Error { .. }
+        )
+    }
+}
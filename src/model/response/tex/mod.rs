@@ -1 +1,2 @@
-pub mod compile_output;
\ No newline at end of file
+pub mod compile_output;
+pub mod compile_log_event;
\ No newline at end of file
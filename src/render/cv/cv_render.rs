@@ -1,13 +1,17 @@
-use crate::model::{request::cv::render_handle_request::RenderHandleRequest, cv::cv_main::CvMainResp};
+use crate::model::{
+    cv::{custom::cv_custom_section_resp::CvCustomSectionResp, cv_main::CvMainResp},
+    request::cv::render_handle_request::RenderHandleRequest,
+};
 
 pub trait CvRender {
     fn gen_cv_start(&self,request: &RenderHandleRequest) ->String;
-    fn _gen_summary(&self,file_path: &str,tpl_code: String) -> String;
+    /// the summary was trimmed and not empty
+    fn gen_summary(&self, summary: &str) -> String;
     fn gen_edu(&self,file_path: &str, cv_main: &CvMainResp) -> String;
     fn gen_work(&self, cv_main: &CvMainResp) -> String;
     fn gen_skill(&self, cv_main: &CvMainResp) -> String;
     fn gen_project(&self, cv_main: &CvMainResp) -> String;
     fn gen_lang(&self, cv_main: &CvMainResp) -> String;
-    fn _gen_section(&self,file_path: &str) -> bool;
+    fn gen_custom_section(&self, section: &CvCustomSectionResp) -> String;
     fn gen_cv_end(&self,file_path: &str,tpl_code: String) -> bool;
 }
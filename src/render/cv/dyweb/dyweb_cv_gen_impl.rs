@@ -1,16 +1,35 @@
 use super::dyweb_cv_util::{
-    get_dyweb_edu_str, get_dyweb_project_str, get_dyweb_skill_str, get_dyweb_work_str, get_lang_skill_str,
+    gen_dyweb_work_items, get_dyweb_edu_str, get_dyweb_project_str, get_dyweb_skill_str,
+    get_dyweb_work_str, get_lang_skill_str,
 };
 use crate::{
-    model::{cv::cv_main::CvMainResp, request::cv::render_handle_request::RenderHandleRequest},
-    render::cv::cv_render::CvRender,
+    model::{
+        cv::{
+            custom::cv_custom_section_resp::CvCustomSectionResp, cv_locale::CvLocale,
+            cv_main::CvMainResp,
+        },
+        request::cv::render_handle_request::RenderHandleRequest,
+    },
+    render::cv::{
+        cv_render::CvRender,
+        section::{
+            cv_label::{cv_label, CvLabel},
+            cv_section::is_item_list,
+        },
+    },
+    util::{
+        cv_util::{get_photo_str, present_field},
+        name_util::CvName,
+    },
 };
 use std::{
     fs::{self, OpenOptions},
     io::Write,
 };
 
-pub struct DywebCvGenImpl {}
+pub struct DywebCvGenImpl {
+    pub locale: CvLocale,
+}
 
 impl DywebCvGenImpl {}
 
@@ -20,34 +39,83 @@ impl CvRender for DywebCvGenImpl {
             fs::remove_file(request.file_path).unwrap();
         }
         let cv_main = &request.cv_main;
+        let (first_name, last_name) = CvName::from_cv(cv_main).display_pair();
+        let mut contacts: Vec<String> = Vec::new();
+        if let Some(email) = present_field(&cv_main.email) {
+            contacts.push(format!(
+                "{}{}{}{}{}",
+                "\\href{mailto:", email, "}{", email, "}"
+            ));
+        }
+        if let Some(phone) = present_field(&cv_main.phone) {
+            contacts.push(phone.to_owned());
+        }
+        if let Some(location) = present_field(&cv_main.location) {
+            contacts.push(location.to_owned());
+        }
+        for link in [
+            &cv_main.github,
+            &cv_main.homepage,
+            &cv_main.blog,
+            &cv_main.linkedin,
+        ] {
+            if let Some(url) = present_field(link) {
+                contacts.push(format!("{}{}{}", "\\url{", url, "}"));
+            }
+        }
         let message = format!(
-            "{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}",
             "\\documentclass{deedy-resume-openfont}\n\n",
-            "\\usepackage{fancyhdr}\n\n",
+            "\\usepackage{fancyhdr}\n",
+            if request.photo_path.is_some() {
+                "\\usepackage{graphicx}\n"
+            } else {
+                ""
+            },
+            "\n",
             "\\pagestyle{fancy}\n",
             "\\fancyhf{}\n\n",
             "\\begin{document}\n\n",
             "\\namesection{",
-            cv_main.employee_name.clone().unwrap(),
-            "}{}",
-            "{\\urlstyle{same}\\href{",
-            cv_main.email.clone().unwrap(),
+            first_name,
             "}{",
-            cv_main.email.clone().unwrap(),
-            "} | ",
-            cv_main.phone.clone().unwrap(),
-            " } \n\n"
+            last_name,
+            "}",
+            "{\\urlstyle{same}",
+            contacts.join(" | "),
+            " } \n\n",
+            get_photo_str(&request.photo_path)
         );
         return message;
     }
 
-    fn _gen_summary(&self, _file_path: &str, tpl_code: String) -> String {
-        let message = format!("{}", tpl_code);
+    fn gen_summary(&self, summary: &str) -> String {
+        let message = format!(
+            "{}{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Summary),
+            "}\n\\sectionsep\n\n",
+            summary,
+            "\n\\sectionsep\n\n"
+        );
         return message;
     }
 
-    fn _gen_section(&self, _file_path: &str) -> bool {
-        todo!()
+    fn gen_custom_section(&self, section: &CvCustomSectionResp) -> String {
+        let content = section.content.as_deref().unwrap_or_default().trim();
+        if content.is_empty() {
+            return "".to_owned();
+        }
+        let items = if is_item_list(content) {
+            gen_dyweb_work_items(content.to_string())
+        } else {
+            format!("{}{}", content, "\n\\sectionsep\n\n")
+        };
+        let message = format!(
+            "{}{}{}{}",
+            "\\section{", section.title, "}\n\\sectionsep\n\n", items
+        );
+        return message;
     }
 
     fn gen_cv_end(&self, file_path: &str, tpl_code: String) -> bool {
@@ -65,8 +133,11 @@ impl CvRender for DywebCvGenImpl {
     fn gen_edu(&self, _file_path: &str, cv_main: &CvMainResp) -> String {
         let edu_items = get_dyweb_edu_str(&cv_main.edu);
         let message = format!(
-            "{}{}",
-            "\\begin{minipage}[t]{0.25\\textwidth}\n\n \\section{教育经历}\n\\sectionsep\n\n", edu_items
+            "{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Edu),
+            "}\n\\sectionsep\n\n",
+            edu_items
         );
         return message;
     }
@@ -74,21 +145,36 @@ impl CvRender for DywebCvGenImpl {
     fn gen_work(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_dyweb_work_str(&cv_main.work);
         let message = format!(
-            "{}{}",
-            "\\hfill\n \\begin{minipage}[t]{0.73\\textwidth}\n\n \\section{工作经历}\n\\sectionsep\n\n", work_items
+            "{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Work),
+            "}\n\\sectionsep\n\n",
+            work_items
         );
         return message;
     }
 
     fn gen_skill(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_dyweb_skill_str(&cv_main.skills);
-        let message = format!("{}{}", "\\section{专业技能}\n\\sectionsep\n\n", work_items);
+        let message = format!(
+            "{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Skill),
+            "}\n\\sectionsep\n\n",
+            work_items
+        );
         return message;
     }
 
     fn gen_project(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_dyweb_project_str(&cv_main.projects);
-        let message = format!("{}{}", "\\section{项目经历}\n\\sectionsep\n\n", work_items);
+        let message = format!(
+            "{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Project),
+            "}\n\\sectionsep\n\n",
+            work_items
+        );
         return message;
     }
 
@@ -100,8 +186,11 @@ impl CvRender for DywebCvGenImpl {
                 }
                 let work_items = get_lang_skill_str(&cv_main.langs);
                 let message = format!(
-                    "{}{}",
-                    "\\section{语言技能}\n\\sectionsep\n\n", work_items
+                    "{}{}{}{}",
+                    "\\section{",
+                    cv_label(self.locale, CvLabel::Lang),
+                    "}\n\\sectionsep\n\n",
+                    work_items
                 );
                 return message;
             }
@@ -98,7 +98,6 @@ pub fn get_dyweb_skill_str(works: &Option<Vec<CvSkillResp>>) -> String {
                 )
                 .to_string();
             }
-            s += &format!("{}","\\end{minipage}\n").to_string();
             return s;
         }
         None => return "".to_owned(),
@@ -1,9 +1,10 @@
-use std::collections::HashMap;
-
 use crate::{
     model::{cv::cv_main::CvMainResp, request::cv::render_handle_request::RenderHandleRequest},
     render::cv::{
-        handler::template_handler::TemplateHandler, cv_render::CvRender, dyweb::dyweb_cv_gen_impl::DywebCvGenImpl,
+        cv_render::CvRender,
+        dyweb::dyweb_cv_gen_impl::DywebCvGenImpl,
+        handler::template_handler::TemplateHandler,
+        section::cv_section::{render_sections, resolve_sections, CvSection},
     },
 };
 
@@ -19,25 +20,24 @@ impl TemplateHandler for DywebHandler {
     ) -> Result<(), &'static str> {
         if request.template_code == "dyweb" {
             println!("Dyweb handle request: {}", request.template_code);
-            let mut cv_map: HashMap<i32, &String> = HashMap::new();
-            let modern = DywebCvGenImpl {};
-            let start = modern.gen_cv_start(&request);
-            let edu = modern.gen_edu(&request.file_path, cv_main);
-            let work = modern.gen_work(cv_main);
-            let skill = modern.gen_skill(cv_main);
-            let project = modern.gen_project(cv_main);
-            let lang = modern.gen_lang(cv_main);
-            cv_map.insert(2, &edu);
-            cv_map.insert(3, &skill);
-            cv_map.insert(4, &work);
-            cv_map.insert(5, &project);
-            cv_map.insert(6, &lang);
-            let mut content = String::from(start);
-            content.push_str(cv_map.get(&2).unwrap());
-            content.push_str(cv_map.get(&3).unwrap());
-            content.push_str(cv_map.get(&4).unwrap());
-            content.push_str(cv_map.get(&5).unwrap());
-            content.push_str(cv_map.get(&6).unwrap());
+            let modern = DywebCvGenImpl {
+                locale: request.locale,
+            };
+            // dyweb was a two column layout, the education and skill stay in the left column
+            // the item order was applied inside each column
+            let (left, right): (Vec<CvSection>, Vec<CvSection>) = resolve_sections(cv_main)
+                .into_iter()
+                .partition(|section| matches!(section, CvSection::Edu | CvSection::Skill));
+            let mut content = modern.gen_cv_start(&request);
+            content.push_str("\\begin{minipage}[t]{0.25\\textwidth}\n\n");
+            content.push_str(&render_sections(&modern, request.file_path, cv_main, &left));
+            content.push_str("\\end{minipage}\n\\hfill\n\\begin{minipage}[t]{0.73\\textwidth}\n\n");
+            content.push_str(&render_sections(
+                &modern,
+                request.file_path,
+                cv_main,
+                &right,
+            ));
             modern.gen_cv_end(&request.file_path, content);
             Ok(())
         } else {
@@ -6,4 +6,5 @@ pub mod zheyuye;
 pub mod dyweb;
 pub mod rodrigo;
 pub mod weitian;
-pub mod hijiangtao;
\ No newline at end of file
+pub mod hijiangtao;
+pub mod section;
\ No newline at end of file
@@ -4,12 +4,32 @@ use std::{
 };
 
 use crate::{
-    model::{cv::cv_main::CvMainResp, request::cv::render_handle_request::RenderHandleRequest},
-    render::cv::cv_render::CvRender,
-    util::cv_util::{ get_project_str, get_skill_str, get_work_str},
+    model::{
+        cv::{
+            custom::cv_custom_section_resp::CvCustomSectionResp, cv_locale::CvLocale,
+            cv_main::CvMainResp,
+        },
+        request::cv::render_handle_request::RenderHandleRequest,
+    },
+    render::cv::{
+        cv_render::CvRender,
+        section::{
+            cv_label::{cv_label, cv_label_separator, CvLabel},
+            cv_section::is_item_list,
+        },
+    },
+    util::{
+        cv_util::{
+            gen_work_items, get_edu_str, get_lang_str, get_moderncv_contact_str, get_project_str,
+            get_skill_str, get_work_str, present_field,
+        },
+        name_util::CvName,
+    },
 };
 
-pub struct ModernCvGenImpl {}
+pub struct ModernCvGenImpl {
+    pub locale: CvLocale,
+}
 
 impl ModernCvGenImpl {}
 /**
@@ -21,44 +41,52 @@ impl CvRender for ModernCvGenImpl {
         if fs::metadata(request.file_path).is_ok() {
             fs::remove_file(request.file_path).unwrap();
         }
-        let binding = request.cv_main.employee_name.clone().unwrap();
-        let ss = binding.as_str();
-        let first_name: String = ss.chars().skip(0).take(1).collect();
-        let last_name: String = ss.chars().skip(1).take(ss.len() - 1).collect();
+        let (first_name, last_name) = CvName::from_cv(&request.cv_main).display_pair();
         let name = format!("{}{}{}{}{}", "\\name{", first_name, "}{", last_name, "}\n");
         let title = format!("{}{}{}", "\\title{", request.cv_main.cv_name, "}\n");
-        let phone = format!(
-            "{}{}{}",
-            "\\phone[mobile]{",
-            request.cv_main.phone.as_ref().unwrap(),
-            "}\n"
-        );
-        let email = format!(
-            "{}{}{}",
-            "\\email{",
-            request.cv_main.email.as_ref().unwrap(),
-            "}\n"
-        );
-        let stackoverflow = format!(
-            "{}{}{}",
-            "\\social[stackoverflow]{",
-            request.cv_main.stackoverflow.as_deref().unwrap_or_default(),
-            "}\n"
-        );
-        let github = format!(
-            "{}{}{}",
-            "\\social[github]{",
-            request.cv_main.github.as_deref().unwrap_or_default(),
-            "}\n"
-        );
-        let extra = format!(
-            "{}{}{}",
-            "\\extrainfo{出生日期：",
-            request.cv_main.birthday.as_deref().unwrap_or_default(),
-            "}\n"
-        );
+        let cv_main = &request.cv_main;
+        let phone = match present_field(&cv_main.phone) {
+            Some(phone) => format!("{}{}{}", "\\phone[mobile]{", phone, "}\n"),
+            None => String::new(),
+        };
+        let email = match present_field(&cv_main.email) {
+            Some(email) => format!("{}{}{}", "\\email{", email, "}\n"),
+            None => String::new(),
+        };
+        let stackoverflow = match present_field(&cv_main.stackoverflow) {
+            Some(so) => format!("{}{}{}", "\\social[stackoverflow]{", so, "}\n"),
+            None => String::new(),
+        };
+        let github = match present_field(&cv_main.github) {
+            Some(github) => format!("{}{}{}", "\\social[github]{", github, "}\n"),
+            None => String::new(),
+        };
+        // moderncv only have one extra info line, put the birthday and blog together
+        let mut extra_items: Vec<String> = Vec::new();
+        if let Some(birthday) = present_field(&cv_main.birthday) {
+            extra_items.push(format!(
+                "{}{}{}",
+                cv_label(self.locale, CvLabel::Birthday),
+                cv_label_separator(self.locale),
+                birthday
+            ));
+        }
+        if let Some(blog) = present_field(&cv_main.blog) {
+            extra_items.push(format!("{}{}{}", "\\url{", blog, "}"));
+        }
+        let extra = if extra_items.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}{}", "\\extrainfo{", extra_items.join(" \\\\ "), "}\n")
+        };
+        // the english cv use the latin modern font only
+        let ctex = if self.locale.need_cjk() {
+            "\\usepackage{ctex}\n"
+        } else {
+            ""
+        };
         let message = format!(
-            "{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}",
             "\\documentclass[",
             request
                 .cv_main
@@ -83,7 +111,7 @@ impl CvRender for ModernCvGenImpl {
             // https://tex.stackexchange.com/questions/532114/use-moderncv-casual-icons-in-moderncv-classic-layout
             "\\moderncvicons{awesome}\n\n",
             // https://tex.stackexchange.com/questions/687144/missing-character-there-is-no-%e8%92%8b-u848b-in-font-lmsans17-regularmapping-tex
-            "\\usepackage{ctex}\n",
+            ctex,
             "\\usepackage{fontspec}\n",
             "\\usepackage[scale=0.75]{geometry}\n\n",
             "\\setmainfont{lmroman10-regular.otf}\n",
@@ -96,19 +124,43 @@ impl CvRender for ModernCvGenImpl {
             stackoverflow,
             github,
             extra,
+            get_moderncv_contact_str(cv_main, &request.photo_path),
             "\n\\begin{document}\n\n",
             "\\makecvtitle\n\n"
         );
         return message;
     }
 
-    fn _gen_summary(&self, _file_path: &str, tpl_code: String) -> String {
-        let message = format!("{}", tpl_code);
+    fn gen_summary(&self, summary: &str) -> String {
+        let message = format!(
+            "{}{}{}{}{}{}",
+            "\n\\section{",
+            cv_label(self.locale, CvLabel::Summary),
+            "}\n\n",
+            "\\cvitem{}{",
+            summary,
+            "}\n\n"
+        );
         return message;
     }
 
-    fn _gen_section(&self, _file_path: &str) -> bool {
-        todo!()
+    fn gen_custom_section(&self, section: &CvCustomSectionResp) -> String {
+        let content = section.content.as_deref().unwrap_or_default().trim();
+        if content.is_empty() {
+            return "".to_owned();
+        }
+        let items = if is_item_list(content) {
+            format!(
+                "{}{}{}",
+                "\\cvitem{}{",
+                gen_work_items(content.to_string()),
+                "}\n\n"
+            )
+        } else {
+            format!("{}{}{}", "\\cvitem{}{", content, "}\n\n")
+        };
+        let message = format!("{}{}{}{}", "\n\\section{", section.title, "}\n\n", items);
+        return message;
     }
 
     fn gen_cv_end(&self, file_path: &str, tpl_code: String) -> bool {
@@ -124,25 +176,53 @@ impl CvRender for ModernCvGenImpl {
     }
 
     fn gen_edu(&self, _file_path: &str, cv_main: &CvMainResp) -> String {
-        let message = format!("{}", "\n\\section{教育经历}\n\n");
+        let edu_items = get_edu_str(&cv_main.edu);
+        if edu_items.is_empty() {
+            return "".to_owned();
+        }
+        let message = format!(
+            "{}{}{}{}",
+            "\n\\section{",
+            cv_label(self.locale, CvLabel::Edu),
+            "}\n\n",
+            edu_items
+        );
         return message;
     }
 
     fn gen_work(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_work_str(&cv_main.work);
-        let message = format!("{}{}", "\n\\section{工作经历}\n\n", work_items);
+        let message = format!(
+            "{}{}{}{}",
+            "\n\\section{",
+            cv_label(self.locale, CvLabel::Work),
+            "}\n\n",
+            work_items
+        );
         return message;
     }
 
     fn gen_skill(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_skill_str(&cv_main.skills);
-        let message = format!("{}{}", "\n\\section{专业技能}\n\n", work_items);
+        let message = format!(
+            "{}{}{}{}",
+            "\n\\section{",
+            cv_label(self.locale, CvLabel::Skill),
+            "}\n\n",
+            work_items
+        );
         return message;
     }
 
     fn gen_project(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_project_str(&cv_main.projects);
-        let message = format!("{}{}", "\n\\section{项目经历}\n\n", work_items);
+        let message = format!(
+            "{}{}{}{}",
+            "\n\\section{",
+            cv_label(self.locale, CvLabel::Project),
+            "}\n\n",
+            work_items
+        );
         return message;
     }
 
@@ -152,8 +232,14 @@ impl CvRender for ModernCvGenImpl {
                 if langs.len() == 0 {
                     return "".to_owned();
                 }
-                let lang_items = "";
-                let message = format!("{}{}", "\n\\section{语言技能}\n\n", lang_items);
+                let lang_items = get_lang_str(&cv_main.langs);
+                let message = format!(
+                    "{}{}{}{}",
+                    "\n\\section{",
+                    cv_label(self.locale, CvLabel::Lang),
+                    "}\n\n",
+                    lang_items
+                );
                 return message;
             }
             None => {
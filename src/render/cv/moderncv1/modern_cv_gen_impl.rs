@@ -3,9 +3,33 @@ use std::{
     io::Write,
 };
 
-use crate::{render::cv::cv_render::CvRender, model::{request::cv::render_handle_request::RenderHandleRequest, cv::cv_main::CvMainResp}};
+use crate::{
+    model::{
+        cv::{
+            custom::cv_custom_section_resp::CvCustomSectionResp, cv_locale::CvLocale,
+            cv_main::CvMainResp,
+        },
+        request::cv::render_handle_request::RenderHandleRequest,
+    },
+    render::cv::{
+        cv_render::CvRender,
+        section::{
+            cv_label::{cv_label, CvLabel},
+            cv_section::is_item_list,
+        },
+    },
+    util::{
+        cv_util::{
+            gen_work_items, get_edu_str, get_lang_str, get_moderncv_contact_str, get_project_str,
+            get_skill_str, get_work_str,
+        },
+        name_util::CvName,
+    },
+};
 
-pub struct ModernCvGenImpl {}
+pub struct ModernCvGenImpl {
+    pub locale: CvLocale,
+}
 
 impl ModernCvGenImpl {
     
@@ -19,53 +43,140 @@ impl CvRender for ModernCvGenImpl {
         if fs::metadata(request.file_path).is_ok() {
             fs::remove_file(request.file_path).unwrap();
         }
+        let (first_name, last_name) = CvName::from_cv(&request.cv_main).display_pair();
         let message = format!(
-            "{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}",
             "\\documentclass[11pt,a4paper,sans]{moderncv}\n",
             "\\moderncvstyle{classic}\n",
-            "\\name{John}{Doe}\n",
+            if self.locale.need_cjk() {
+                "\\usepackage{ctex}\n"
+            } else {
+                ""
+            },
+            "\\name{",
+            first_name,
+            "}{",
+            last_name,
+            "}\n",
+            get_moderncv_contact_str(&request.cv_main, &request.photo_path),
             "\\begin{document}\n"
         );
         return message;
     }
 
-    fn _gen_summary(&self, _file_path: &str, _tpl_code: String) ->String {
-        todo!()
+    fn gen_summary(&self, summary: &str) -> String {
+        let message = format!(
+            "{}{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Summary),
+            "}\n\\cvitem{}{",
+            summary,
+            "}\n"
+        );
+        return message;
     }
 
-    fn _gen_section(&self, _file_path: &str) -> bool {
-        todo!()
+    fn gen_custom_section(&self, section: &CvCustomSectionResp) -> String {
+        let content = section.content.as_deref().unwrap_or_default().trim();
+        if content.is_empty() {
+            return "".to_owned();
+        }
+        let items = if is_item_list(content) {
+            gen_work_items(content.to_string())
+        } else {
+            content.to_owned()
+        };
+        let message = format!(
+            "{}{}{}{}{}",
+            "\\section{", section.title, "}\n\\cvitem{}{", items, "}\n"
+        );
+        return message;
     }
 
-    fn gen_cv_end(&self, file_path: &str,_tpl_code: String) -> bool {
+    fn gen_cv_end(&self, file_path: &str, tpl_code: String) -> bool {
         let mut file = OpenOptions::new()
+            .create(true)
             .write(true)
             .append(true)
             .open(file_path)
             .unwrap();
-        let message = format!("{}{}", "\n", "\\end{document}");
+        let message = format!("{}{}{}", tpl_code, "\n", "\\end{document}");
         file.write_all(message.as_bytes()).unwrap();
         return true;
     }
 
-    fn gen_edu(&self,_file_path: &str, _cv_main: &CvMainResp) -> String {
-        let message = format!("{}", "\\section{Education}\n");
+    fn gen_edu(&self, _file_path: &str, cv_main: &CvMainResp) -> String {
+        let edu_items = get_edu_str(&cv_main.edu);
+        if edu_items.is_empty() {
+            return "".to_owned();
+        }
+        let message = format!(
+            "{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Edu),
+            "}\n",
+            edu_items
+        );
         return message;
     }
 
-    fn gen_work(&self,_cv_main: &CvMainResp) -> String {
-        todo!()
+    fn gen_work(&self, cv_main: &CvMainResp) -> String {
+        let work_items = get_work_str(&cv_main.work);
+        if work_items.is_empty() {
+            return "".to_owned();
+        }
+        let message = format!(
+            "{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Work),
+            "}\n",
+            work_items
+        );
+        return message;
     }
 
-    fn gen_skill(&self,_cv_main: &CvMainResp) -> String {
-        todo!()
+    fn gen_skill(&self, cv_main: &CvMainResp) -> String {
+        let skill_items = get_skill_str(&cv_main.skills);
+        if skill_items.is_empty() {
+            return "".to_owned();
+        }
+        let message = format!(
+            "{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Skill),
+            "}\n",
+            skill_items
+        );
+        return message;
     }
 
-    fn gen_project(&self,_cv_main: &CvMainResp) -> String {
-        todo!()
+    fn gen_project(&self, cv_main: &CvMainResp) -> String {
+        let project_items = get_project_str(&cv_main.projects);
+        if project_items.is_empty() {
+            return "".to_owned();
+        }
+        let message = format!(
+            "{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Project),
+            "}\n",
+            project_items
+        );
+        return message;
     }
 
-    fn gen_lang(&self, _cv_main: &CvMainResp) -> String {
-        todo!()
+    fn gen_lang(&self, cv_main: &CvMainResp) -> String {
+        let lang_items = get_lang_str(&cv_main.langs);
+        if lang_items.is_empty() {
+            return "".to_owned();
+        }
+        let message = format!(
+            "{}{}{}{}",
+            "\\section{",
+            cv_label(self.locale, CvLabel::Lang),
+            "}\n",
+            lang_items
+        );
+        return message;
     }
 }
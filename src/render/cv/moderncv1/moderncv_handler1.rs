@@ -14,8 +14,9 @@ impl TemplateHandler for ModerncvHandler1 {
         if request.template_code == "moderncv" {
             println!("ConcreteHandler1 handle request: {}", request.template_code);
             //modern.gen_cv_start(&request);
+            return Ok(());
         }
-        Ok(())
+        Err("No handler can handle this request.")
     }
 
     fn _set_next(&mut self, _handler: Box<dyn TemplateHandler>) {}
@@ -1,5 +1,5 @@
 use crate::model::cv::{
-    edu::edu::CvEduResp, project::cv_project_resp::CvProjectResp,
+    edu::edu::CvEduResp, lang::cv_lang_resp::CvLangResp, project::cv_project_resp::CvProjectResp,
     skill::cv_skill_resp::CvSkillResp, work::cv_work_resp::CvWorkResp,
 };
 
@@ -110,6 +110,30 @@ pub fn get_rodrigo_skill_str(works: &Option<Vec<CvSkillResp>>) -> String {
     }
 }
 
+pub fn get_rodrigo_lang_str(works: &Option<Vec<CvLangResp>>) -> String {
+    match works {
+        Some(edu) => {
+            let mut s = String::from("\\begin{itemize}[parsep=0.5ex]\n");
+            for i in edu {
+                s += &format!(
+                    "{}{}{}{}{}{}{}",
+                    "\\item {",
+                    i.name.clone(),
+                    ": ",
+                    i.level.as_deref().unwrap_or_default(),
+                    "(",
+                    i.memo.as_deref().unwrap_or_default(),
+                    ")}\n"
+                )
+                .to_string();
+            }
+            s += &format!("{}", "\\end{itemize}\n\n").to_string();
+            return s;
+        }
+        None => return "".to_owned(),
+    }
+}
+
 pub fn get_rodrigo_project_str(works: &Option<Vec<CvProjectResp>>) -> String {
     match works {
         Some(edu) => {
@@ -0,0 +1,49 @@
+use crate::model::cv::cv_locale::CvLocale;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvLabel {
+    Summary,
+    Edu,
+    Work,
+    Skill,
+    Project,
+    Lang,
+    Birthday,
+}
+
+/**
+ * the localisation table of the cv headings and labels
+ */
+pub fn cv_label(locale: CvLocale, label: CvLabel) -> &'static str {
+    match (locale, label) {
+        (CvLocale::Zh, CvLabel::Summary) => "个人简介",
+        (CvLocale::Zh, CvLabel::Edu) => "教育经历",
+        (CvLocale::Zh, CvLabel::Work) => "工作经历",
+        (CvLocale::Zh, CvLabel::Skill) => "专业技能",
+        (CvLocale::Zh, CvLabel::Project) => "项目经历",
+        (CvLocale::Zh, CvLabel::Lang) => "语言技能",
+        (CvLocale::Zh, CvLabel::Birthday) => "出生日期",
+        (CvLocale::En, CvLabel::Summary) => "Summary",
+        (CvLocale::En, CvLabel::Edu) => "Education",
+        (CvLocale::En, CvLabel::Work) => "Work Experience",
+        (CvLocale::En, CvLabel::Skill) => "Skills",
+        (CvLocale::En, CvLabel::Project) => "Projects",
+        (CvLocale::En, CvLabel::Lang) => "Languages",
+        (CvLocale::En, CvLabel::Birthday) => "Date of Birth",
+        (CvLocale::Bilingual, CvLabel::Summary) => "个人简介 Summary",
+        (CvLocale::Bilingual, CvLabel::Edu) => "教育经历 Education",
+        (CvLocale::Bilingual, CvLabel::Work) => "工作经历 Work Experience",
+        (CvLocale::Bilingual, CvLabel::Skill) => "专业技能 Skills",
+        (CvLocale::Bilingual, CvLabel::Project) => "项目经历 Projects",
+        (CvLocale::Bilingual, CvLabel::Lang) => "语言技能 Languages",
+        (CvLocale::Bilingual, CvLabel::Birthday) => "出生日期 Date of Birth",
+    }
+}
+
+/// the separator between the label and the value
+pub fn cv_label_separator(locale: CvLocale) -> &'static str {
+    match locale {
+        CvLocale::En => ": ",
+        _ => "：",
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::HashSet;
+
+use log::warn;
+
+use crate::{
+    model::{cv::cv_main::CvMainResp, request::cv::render_handle_request::RenderHandleRequest},
+    render::cv::cv_render::CvRender,
+};
+
+/**
+ * the section ids used by `CvMainResp.item_order` and `CvMainResp.hidden_items`
+ * the basic info(1) was always rendered by `gen_cv_start`
+ * any other id was treated as a custom section item id
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CvSection {
+    Basic,
+    Edu,
+    Work,
+    Skill,
+    Project,
+    Lang,
+    Summary,
+    Custom(i32),
+}
+
+impl From<i32> for CvSection {
+    fn from(item_id: i32) -> Self {
+        match item_id {
+            1 => CvSection::Basic,
+            2 => CvSection::Edu,
+            3 => CvSection::Work,
+            4 => CvSection::Skill,
+            5 => CvSection::Project,
+            6 => CvSection::Lang,
+            7 => CvSection::Summary,
+            _ => CvSection::Custom(item_id),
+        }
+    }
+}
+
+impl From<CvSection> for i32 {
+    fn from(section: CvSection) -> Self {
+        match section {
+            CvSection::Basic => 1,
+            CvSection::Edu => 2,
+            CvSection::Work => 3,
+            CvSection::Skill => 4,
+            CvSection::Project => 5,
+            CvSection::Lang => 6,
+            CvSection::Summary => 7,
+            CvSection::Custom(item_id) => item_id,
+        }
+    }
+}
+
+/// the order used when the cv did not specify any item order, the sections missing from
+/// the specified order were appended in this order
+const DEFAULT_ITEM_ORDER: [i32; 6] = [7, 2, 3, 4, 5, 6];
+
+/**
+ * parse the comma separated item ids, the blank and non-numeric entry will be skipped
+ * the duplicate item only keep the first one
+ */
+pub fn parse_item_ids(item_ids: &str) -> Vec<i32> {
+    let mut seen: HashSet<i32> = HashSet::new();
+    let mut ids: Vec<i32> = Vec::new();
+    for raw in item_ids.split(",") {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        match trimmed.parse::<i32>() {
+            Ok(id) => {
+                if seen.insert(id) {
+                    ids.push(id);
+                }
+            }
+            Err(e) => {
+                warn!(
+                    "skip invalid cv item id: {}, item ids: {}, error: {}",
+                    trimmed, item_ids, e
+                );
+            }
+        }
+    }
+    return ids;
+}
+
+/**
+ * resolve the sections to render in order, the sections not in the item order were appended
+ * the hidden sections, the basic info and the unknown ids will be removed
+ */
+pub fn resolve_sections(cv_main: &CvMainResp) -> Vec<CvSection> {
+    let hidden: HashSet<i32> = parse_item_ids(cv_main.hidden_items.as_deref().unwrap_or_default())
+        .into_iter()
+        .collect();
+    let mut order = parse_item_ids(&cv_main.item_order);
+    // the order stored before the summary or a custom section was added did not list them
+    let mut missing: Vec<i32> = DEFAULT_ITEM_ORDER.to_vec();
+    if let Some(customs) = &cv_main.custom_sections {
+        missing.extend(customs.iter().map(|c| c.item_id));
+    }
+    missing.retain(|item_id| !order.contains(item_id));
+    order.extend(missing);
+    order
+        .into_iter()
+        .filter(|item_id| !hidden.contains(item_id))
+        .map(CvSection::from)
+        .filter(|section| match section {
+            CvSection::Basic => false,
+            CvSection::Custom(item_id) => {
+                let exists = cv_main.custom_sections.as_ref().map_or(false, |customs| {
+                    customs.iter().any(|c| c.item_id == *item_id)
+                });
+                if !exists {
+                    warn!(
+                        "skip unknown cv item id: {}, cv id: {}",
+                        item_id, cv_main.id
+                    );
+                }
+                exists
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+pub fn render_section(
+    render: &dyn CvRender,
+    file_path: &str,
+    cv_main: &CvMainResp,
+    section: CvSection,
+) -> String {
+    match section {
+        CvSection::Basic => "".to_owned(),
+        CvSection::Edu => render.gen_edu(file_path, cv_main),
+        CvSection::Work => render.gen_work(cv_main),
+        CvSection::Skill => render.gen_skill(cv_main),
+        CvSection::Project => render.gen_project(cv_main),
+        CvSection::Lang => render.gen_lang(cv_main),
+        CvSection::Summary => {
+            let summary = cv_main.summary.as_deref().unwrap_or_default().trim();
+            if summary.is_empty() {
+                return "".to_owned();
+            }
+            render.gen_summary(summary)
+        }
+        CvSection::Custom(item_id) => match &cv_main.custom_sections {
+            Some(customs) => customs
+                .iter()
+                .find(|c| c.item_id == item_id)
+                .map(|c| render.gen_custom_section(c))
+                .unwrap_or_default(),
+            None => "".to_owned(),
+        },
+    }
+}
+
+pub fn render_sections(
+    render: &dyn CvRender,
+    file_path: &str,
+    cv_main: &CvMainResp,
+    sections: &[CvSection],
+) -> String {
+    let mut content = String::new();
+    for section in sections {
+        content.push_str(&render_section(render, file_path, cv_main, *section));
+    }
+    return content;
+}
+
+/**
+ * generate the whole cv with the sections ordered by the cv item order
+ */
+pub fn render_ordered_cv(
+    render: &dyn CvRender,
+    request: &RenderHandleRequest,
+    cv_main: &CvMainResp,
+) {
+    let mut content = render.gen_cv_start(request);
+    let sections = resolve_sections(cv_main);
+    content.push_str(&render_sections(
+        render,
+        request.file_path,
+        cv_main,
+        &sections,
+    ));
+    render.gen_cv_end(request.file_path, content);
+}
+
+/// check the user input content was a `* ` prefixed item list
+pub fn is_item_list(content: &str) -> bool {
+    content.trim_start().starts_with("* ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::cv::{custom::cv_custom_section_resp::CvCustomSectionResp, cv_locale::CvLocale},
+        render::cv::{
+            dyweb::{dyweb_cv_gen_impl::DywebCvGenImpl, dyweb_handler::DywebHandler},
+            handler::template_handler::TemplateHandler,
+            hijiangtao::{
+                hijiangtao_cv_gen_impl::HijiangtaoCvGenImpl, hijiangtao_handler::HijiangtaoHandler,
+            },
+            moderncv::{modern_cv_gen_impl::ModernCvGenImpl, moderncv_handler::ModerncvHandler},
+            moderncv1::modern_cv_gen_impl::ModernCvGenImpl as ModernCvGenImpl1,
+            rodrigo::{rodrigo_cv_gen_impl::RodrigoCvGenImpl, rodrigo_handler::RodrigoHandler},
+            weitian::{weitian_cv_gen_impl::WeitianCvGenImpl, weitian_handler::WeitianHandler},
+            zheyuye::{zheyuye_cv_gen_impl::ZheyuyeCvGenImpl, zheyuye_handler::ZheyuyeHandler},
+        },
+    };
+    use std::{env, fs};
+
+    fn custom_section(item_id: i32, title: &str) -> CvCustomSectionResp {
+        return CvCustomSectionResp {
+            item_id: item_id,
+            title: title.to_owned(),
+            content: Some(format!("{} content", title)),
+            ..Default::default()
+        };
+    }
+
+    fn sample_cv(item_order: &str, hidden_items: Option<&str>) -> CvMainResp {
+        return CvMainResp {
+            id: 1,
+            cv_name: "Engineer".to_owned(),
+            employee_name: Some("Jane Doe".to_owned()),
+            item_order: item_order.to_owned(),
+            hidden_items: hidden_items.map(|h| h.to_owned()),
+            summary: Some("Summary content".to_owned()),
+            custom_sections: Some(vec![
+                custom_section(101, "Awards"),
+                custom_section(102, "Hobbies"),
+            ]),
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn parse_item_ids_skips_blank_invalid_and_duplicate_ids() {
+        assert_eq!(parse_item_ids(" 3, ,x,5,3,7 "), vec![3, 5, 7]);
+        assert!(parse_item_ids("").is_empty());
+    }
+
+    #[test]
+    fn resolve_sections_uses_default_order_when_empty() {
+        let sections = resolve_sections(&sample_cv("", None));
+        assert_eq!(
+            sections,
+            vec![
+                CvSection::Summary,
+                CvSection::Edu,
+                CvSection::Work,
+                CvSection::Skill,
+                CvSection::Project,
+                CvSection::Lang,
+                CvSection::Custom(101),
+                CvSection::Custom(102),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_sections_drops_basic_hidden_and_unknown_ids() {
+        let sections = resolve_sections(&sample_cv("1,5,102,999,3,101,2,4,6,7", Some("2,102")));
+        assert_eq!(
+            sections,
+            vec![
+                CvSection::Project,
+                CvSection::Work,
+                CvSection::Custom(101),
+                CvSection::Skill,
+                CvSection::Lang,
+                CvSection::Summary,
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_sections_appends_sections_missing_from_stored_order() {
+        // the order stored before the summary and the custom sections existed
+        let sections = resolve_sections(&sample_cv("1,2,3,4,5,6", Some("102")));
+        assert_eq!(
+            sections,
+            vec![
+                CvSection::Edu,
+                CvSection::Work,
+                CvSection::Skill,
+                CvSection::Project,
+                CvSection::Lang,
+                CvSection::Summary,
+                CvSection::Custom(101),
+            ]
+        );
+    }
+
+    fn render_tex(
+        template_code: &str,
+        handler: &dyn TemplateHandler,
+        cv_main: &CvMainResp,
+    ) -> String {
+        let file_path = env::temp_dir().join(format!(
+            "cv-section-{}-{}.tex",
+            template_code,
+            uuid::Uuid::new_v4()
+        ));
+        let file_path = file_path.to_string_lossy().to_string();
+        let request = RenderHandleRequest {
+            template_code: template_code.to_owned(),
+            file_path: &file_path,
+            cv_main: cv_main.clone(),
+            locale: CvLocale::En,
+            photo_path: None,
+        };
+        handler.handle_request(request, cv_main).unwrap();
+        let tex = fs::read_to_string(&file_path).unwrap();
+        let _ = fs::remove_file(&file_path);
+        return tex;
+    }
+
+    fn position(tex: &str, heading: &str, template_code: &str) -> usize {
+        return tex
+            .find(heading)
+            .unwrap_or_else(|| panic!("{} missing in the {} tex", heading, template_code));
+    }
+
+    #[test]
+    fn every_template_renders_sections_in_item_order() {
+        let handlers: Vec<(&str, Box<dyn TemplateHandler>)> = vec![
+            ("moderncv", Box::new(ModerncvHandler { next: None })),
+            ("zheyuye", Box::new(ZheyuyeHandler { next: None })),
+            ("dyweb", Box::new(DywebHandler { next: None })),
+            ("weitian", Box::new(WeitianHandler { next: None })),
+            ("hijiangtao", Box::new(HijiangtaoHandler { next: None })),
+            ("rodrigo", Box::new(RodrigoHandler { next: None })),
+        ];
+        let cv_main = sample_cv("3,101,7,5,102,999", Some("102"));
+        for (template_code, handler) in handlers {
+            let tex = render_tex(template_code, handler.as_ref(), &cv_main);
+            let work = position(&tex, "Work Experience", template_code);
+            let awards = position(&tex, "Awards", template_code);
+            let summary = position(&tex, "Summary content", template_code);
+            let project = position(&tex, "Projects", template_code);
+            assert!(
+                work < awards && awards < summary && summary < project,
+                "{} sections out of order",
+                template_code
+            );
+            assert!(
+                !tex.contains("Hobbies"),
+                "{} rendered the hidden section",
+                template_code
+            );
+        }
+    }
+
+    fn every_render() -> Vec<(&'static str, Box<dyn CvRender>)> {
+        let locale = CvLocale::En;
+        return vec![
+            ("moderncv", Box::new(ModernCvGenImpl { locale: locale })),
+            ("moderncv1", Box::new(ModernCvGenImpl1 { locale: locale })),
+            ("zheyuye", Box::new(ZheyuyeCvGenImpl { locale: locale })),
+            ("dyweb", Box::new(DywebCvGenImpl { locale: locale })),
+            ("weitian", Box::new(WeitianCvGenImpl { locale: locale })),
+            (
+                "hijiangtao",
+                Box::new(HijiangtaoCvGenImpl { locale: locale }),
+            ),
+            ("rodrigo", Box::new(RodrigoCvGenImpl { locale: locale })),
+        ];
+    }
+
+    #[test]
+    fn blank_summary_and_custom_section_render_nothing() {
+        let mut cv_main = sample_cv("", None);
+        cv_main.summary = Some("  ".to_owned());
+        cv_main.custom_sections = Some(vec![CvCustomSectionResp {
+            item_id: 101,
+            title: "Awards".to_owned(),
+            content: Some(" \n".to_owned()),
+            ..Default::default()
+        }]);
+        for (template_code, render) in every_render() {
+            for section in [CvSection::Summary, CvSection::Custom(101)] {
+                let tex = render_section(render.as_ref(), "", &cv_main, section);
+                assert_eq!(
+                    tex, "",
+                    "{} rendered the blank {:?}",
+                    template_code, section
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn custom_item_list_renders_as_itemize() {
+        let mut cv_main = sample_cv("", None);
+        cv_main.custom_sections = Some(vec![CvCustomSectionResp {
+            item_id: 101,
+            title: "Awards".to_owned(),
+            content: Some("* first award * second award".to_owned()),
+            ..Default::default()
+        }]);
+        for (template_code, render) in every_render() {
+            let tex = render_section(render.as_ref(), "", &cv_main, CvSection::Custom(101));
+            assert!(
+                tex.contains("\\item first award"),
+                "{} rendered the item list as raw text",
+                template_code
+            );
+        }
+    }
+
+    #[test]
+    fn moderncv_skips_the_empty_education() {
+        let render = ModernCvGenImpl {
+            locale: CvLocale::En,
+        };
+        let tex = render_section(&render, "", &sample_cv("", None), CvSection::Edu);
+        assert_eq!(tex, "");
+    }
+}
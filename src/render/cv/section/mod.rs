@@ -0,0 +1,2 @@
+pub mod cv_section;
+pub mod cv_label;
@@ -4,16 +4,34 @@ use std::{
 };
 
 use crate::{
-    model::{cv::cv_main::CvMainResp, request::cv::render_handle_request::RenderHandleRequest},
-    render::cv::cv_render::CvRender,
+    model::{
+        cv::{
+            custom::cv_custom_section_resp::CvCustomSectionResp, cv_locale::CvLocale,
+            cv_main::CvMainResp,
+        },
+        request::cv::render_handle_request::RenderHandleRequest,
+    },
+    render::cv::{
+        cv_render::CvRender,
+        section::{
+            cv_label::{cv_label, CvLabel},
+            cv_section::is_item_list,
+        },
+    },
+    util::{
+        cv_util::{get_contact_links_str, get_photo_str, present_field},
+        name_util::CvName,
+    },
 };
 
 use super::weitian_cv_util::{
-    get_weitian_edu_str, get_weitian_lang_str, get_weitian_project_str, get_weitian_skill_str,
-    get_weitian_work_str,
+    gen_weitian_work_items, get_weitian_edu_str, get_weitian_lang_str, get_weitian_project_str,
+    get_weitian_skill_str, get_weitian_work_str,
 };
 
-pub struct WeitianCvGenImpl {}
+pub struct WeitianCvGenImpl {
+    pub locale: CvLocale,
+}
 
 impl WeitianCvGenImpl {}
 /**
@@ -25,54 +43,44 @@ impl CvRender for WeitianCvGenImpl {
         if fs::metadata(request.file_path).is_ok() {
             fs::remove_file(request.file_path).unwrap();
         }
-        let binding = request.cv_main.employee_name.clone().unwrap();
-        let ss = binding.as_str();
-        let first_name: String = ss.chars().skip(0).take(1).collect();
-        let last_name: String = ss.chars().skip(1).take(ss.len() - 1).collect();
+        let cv_name = CvName::from_cv(&request.cv_main);
         let name = format!(
             "{}{}{}{}{}",
-            "\\name{", last_name, "}{", first_name, "}\n\n"
+            "\\name{", cv_name.given_name, "}{", cv_name.family_name, "}\n\n"
         );
         let _title = format!("{}{}{}", "\\title{", request.cv_main.cv_name, "}\n");
-        let phone = format!(
-            "{}{}{}",
-            "\\mobile{",
-            request.cv_main.phone.as_ref().unwrap(),
-            "}\n"
-        );
-        let email = format!(
-            "{}{}{}",
-            "\\email{",
-            request.cv_main.email.as_ref().unwrap(),
-            "}\n"
-        );
-        let _stackoverflow = format!(
-            "{}{}{}",
-            "\\social[stackoverflow]{",
-            request.cv_main.stackoverflow.as_deref().unwrap_or_default(),
-            "}\n"
-        );
-        let github = format!(
-            "{}{}{}",
-            "\\github{",
-            request
-                .cv_main
-                .github
-                .as_deref()
-                .unwrap_or_default()
-                .split("/")
-                .last()
-                .unwrap(),
-            "}\n"
-        );
+        let phone = match present_field(&request.cv_main.phone) {
+            Some(phone) => format!("{}{}{}", "\\mobile{", phone, "}\n"),
+            None => String::new(),
+        };
+        let email = match present_field(&request.cv_main.email) {
+            Some(email) => format!("{}{}{}", "\\email{", email, "}\n"),
+            None => String::new(),
+        };
+        let github = match present_field(&request.cv_main.github) {
+            Some(github) => format!(
+                "{}{}{}",
+                "\\github{",
+                github.trim_end_matches("/").split("/").last().unwrap(),
+                "}\n"
+            ),
+            None => String::new(),
+        };
         let message = format!(
-            "{}{}{}{}{}{}{}{}{}{}{}{}{}",
-            "\\documentclass[zh]{weitian-resume}\n\n",
+            "{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}",
+            "\\documentclass[",
+            if self.locale.need_cjk() { "zh" } else { "en" },
+            "]{weitian-resume}\n\n",
+            if request.photo_path.is_some() {
+                "\\usepackage{graphicx}\n"
+            } else {
+                ""
+            },
             "\\iconsize{\\Large}\n",
             "\\fileinfo{\n",
             // https://tex.stackexchange.com/questions/687144/missing-character-there-is-no-%e8%92%8b-u848b-in-font-lmsans17-regularmapping-tex
             "\\faCopyright{} \\the\\year, ",
-            request.cv_main.employee_name.clone().unwrap_or_default(),
+            cv_name.full_name(),
             " \\hspace{0.5em}\n",
             "\\faEdit{} \\today \n}\n\n",
             name,
@@ -81,17 +89,44 @@ impl CvRender for WeitianCvGenImpl {
             email,
             github,
             "}\n\n\\begin{document}\n\\makeheader\n",
+            get_contact_links_str(&request.cv_main),
+            get_photo_str(&request.photo_path),
         );
         return message;
     }
 
-    fn _gen_summary(&self, _file_path: &str, tpl_code: String) -> String {
-        let message = format!("{}", tpl_code);
+    fn gen_summary(&self, summary: &str) -> String {
+        let message = format!(
+            "{}{}{}{}{}",
+            "\\sectionTitle{",
+            cv_label(self.locale, CvLabel::Summary),
+            "}{\\faUser}\n\n",
+            summary,
+            "\n\n"
+        );
         return message;
     }
 
-    fn _gen_section(&self, _file_path: &str) -> bool {
-        todo!()
+    fn gen_custom_section(&self, section: &CvCustomSectionResp) -> String {
+        let content = section.content.as_deref().unwrap_or_default().trim();
+        if content.is_empty() {
+            return "".to_owned();
+        }
+        let items = if is_item_list(content) {
+            format!(
+                "{}{}{}",
+                "\\begin{itemize}\n",
+                gen_weitian_work_items(content.to_string()),
+                "\n\\end{itemize}\n\n"
+            )
+        } else {
+            format!("{}{}", content, "\n\n")
+        };
+        let message = format!(
+            "{}{}{}{}",
+            "\\sectionTitle{", section.title, "}{\\faStar}\n\n", items
+        );
+        return message;
     }
 
     fn gen_cv_end(&self, file_path: &str, tpl_code: String) -> bool {
@@ -109,8 +144,11 @@ impl CvRender for WeitianCvGenImpl {
     fn gen_edu(&self, _file_path: &str, cv_main: &CvMainResp) -> String {
         let edu_items = get_weitian_edu_str(&cv_main.edu);
         let message = format!(
-            "{}{}",
-            "\\sectionTitle{教育经历}{\\faGraduationCap}\n\n", edu_items
+            "{}{}{}{}",
+            "\\sectionTitle{",
+            cv_label(self.locale, CvLabel::Edu),
+            "}{\\faGraduationCap}\n\n",
+            edu_items
         );
         return message;
     }
@@ -118,8 +156,11 @@ impl CvRender for WeitianCvGenImpl {
     fn gen_work(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_weitian_work_str(&cv_main.work);
         let message = format!(
-            "{}{}",
-            "\\sectionTitle{工作经历}{\\faBriefcase}\n\n", work_items
+            "{}{}{}{}",
+            "\\sectionTitle{",
+            cv_label(self.locale, CvLabel::Work),
+            "}{\\faBriefcase}\n\n",
+            work_items
         );
         return message;
     }
@@ -127,15 +168,24 @@ impl CvRender for WeitianCvGenImpl {
     fn gen_skill(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_weitian_skill_str(&cv_main.skills);
         let message = format!(
-            "{}{}",
-            "\\sectionTitle{专业技能}{\\faWrench}\n\n", work_items
+            "{}{}{}{}",
+            "\\sectionTitle{",
+            cv_label(self.locale, CvLabel::Skill),
+            "}{\\faWrench}\n\n",
+            work_items
         );
         return message;
     }
 
     fn gen_project(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_weitian_project_str(&cv_main.projects);
-        let message = format!("{}{}", "\\sectionTitle{项目经历}{\\faCode}\n\n", work_items);
+        let message = format!(
+            "{}{}{}{}",
+            "\\sectionTitle{",
+            cv_label(self.locale, CvLabel::Project),
+            "}{\\faCode}\n\n",
+            work_items
+        );
         return message;
     }
 
@@ -147,8 +197,11 @@ impl CvRender for WeitianCvGenImpl {
                 }
                 let work_items = get_weitian_lang_str(&cv_main.langs);
                 let message = format!(
-                    "{}{}",
-                    "\\sectionTitle{语言技能}{\\faLanguage}\n\n", work_items
+                    "{}{}{}{}",
+                    "\\sectionTitle{",
+                    cv_label(self.locale, CvLabel::Lang),
+                    "}{\\faLanguage}\n\n",
+                    work_items
                 );
                 return message;
             }
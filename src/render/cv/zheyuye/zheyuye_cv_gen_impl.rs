@@ -1,17 +1,35 @@
 use super::zheyuye_cv_util::{
-    get_zheyuye_edu_str, get_zheyuye_lang_str, get_zheyuye_project_str, get_zheyuye_skill_str,
-    get_zheyuye_work_str,
+    gen_zheyuye_work_items, get_zheyuye_edu_str, get_zheyuye_lang_str, get_zheyuye_project_str,
+    get_zheyuye_skill_str, get_zheyuye_work_str,
 };
 use crate::{
-    model::{cv::cv_main::CvMainResp, request::cv::render_handle_request::RenderHandleRequest},
-    render::cv::cv_render::CvRender,
+    model::{
+        cv::{
+            custom::cv_custom_section_resp::CvCustomSectionResp, cv_locale::CvLocale,
+            cv_main::CvMainResp,
+        },
+        request::cv::render_handle_request::RenderHandleRequest,
+    },
+    render::cv::{
+        cv_render::CvRender,
+        section::{
+            cv_label::{cv_label, CvLabel},
+            cv_section::is_item_list,
+        },
+    },
+    util::{
+        cv_util::{get_contact_links_str, get_photo_str, present_field},
+        name_util::CvName,
+    },
 };
 use std::{
     fs::{self, OpenOptions},
     io::Write,
 };
 
-pub struct ZheyuyeCvGenImpl {}
+pub struct ZheyuyeCvGenImpl {
+    pub locale: CvLocale,
+}
 
 impl ZheyuyeCvGenImpl {}
 
@@ -22,30 +40,57 @@ impl CvRender for ZheyuyeCvGenImpl {
         }
         let cv_main = &request.cv_main;
         let message = format!(
-            "{}{}{}{}{}{}{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}",
             "\\documentclass{zheyuyesetting}\n\n",
+            if request.photo_path.is_some() {
+                "\\usepackage{graphicx}\n"
+            } else {
+                ""
+            },
             "\\begin{document}\n\n",
             "\\name{",
-            cv_main.employee_name.clone().unwrap(),
+            CvName::from_cv(cv_main).full_name(),
             "}\n",
             "\\contactInfo{",
-            cv_main.phone.clone().unwrap(),
+            present_field(&cv_main.phone).unwrap_or_default(),
             "}{",
-            cv_main.email.clone().unwrap(),
+            present_field(&cv_main.email).unwrap_or_default(),
             "}{",
-            cv_main.github.clone().unwrap_or_default(),
-            "}\n\n"
+            present_field(&cv_main.github).unwrap_or_default(),
+            "}\n\n",
+            get_contact_links_str(cv_main),
+            get_photo_str(&request.photo_path)
         );
         return message;
     }
 
-    fn _gen_summary(&self, _file_path: &str, tpl_code: String) -> String {
-        let message = format!("{}", tpl_code);
+    fn gen_summary(&self, summary: &str) -> String {
+        let message = format!(
+            "{}{}{}{}{}",
+            "\\logosection{\\faUser}{",
+            cv_label(self.locale, CvLabel::Summary),
+            "}\n\n",
+            summary,
+            "\n\n"
+        );
         return message;
     }
 
-    fn _gen_section(&self, _file_path: &str) -> bool {
-        todo!()
+    fn gen_custom_section(&self, section: &CvCustomSectionResp) -> String {
+        let content = section.content.as_deref().unwrap_or_default().trim();
+        if content.is_empty() {
+            return "".to_owned();
+        }
+        let items = if is_item_list(content) {
+            gen_zheyuye_work_items(content.to_string())
+        } else {
+            format!("{}{}", content, "\n\n")
+        };
+        let message = format!(
+            "{}{}{}{}",
+            "\\logosection{\\faStar}{", section.title, "}\n\n", items
+        );
+        return message;
     }
 
     fn gen_cv_end(&self, file_path: &str, tpl_code: String) -> bool {
@@ -63,8 +108,11 @@ impl CvRender for ZheyuyeCvGenImpl {
     fn gen_edu(&self, _file_path: &str, cv_main: &CvMainResp) -> String {
         let edu_items = get_zheyuye_edu_str(&cv_main.edu);
         let message = format!(
-            "{}{}",
-            "\\logosection{\\faGraduationCap}{教育经历}\n\n", edu_items
+            "{}{}{}{}",
+            "\\logosection{\\faGraduationCap}{",
+            cv_label(self.locale, CvLabel::Edu),
+            "}\n\n",
+            edu_items
         );
         return message;
     }
@@ -72,8 +120,11 @@ impl CvRender for ZheyuyeCvGenImpl {
     fn gen_work(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_zheyuye_work_str(&cv_main.work);
         let message = format!(
-            "{}{}",
-            "\\logosection{\\faSuitcase}{工作经历}\n\n", work_items
+            "{}{}{}{}",
+            "\\logosection{\\faSuitcase}{",
+            cv_label(self.locale, CvLabel::Work),
+            "}\n\n",
+            work_items
         );
         return message;
     }
@@ -83,15 +134,24 @@ impl CvRender for ZheyuyeCvGenImpl {
         if work_items.is_empty() {
             return "".to_owned();
         }
-        let message = format!("{}{}", "\\logosection{\\faCogs}{专业技能}\n\n", work_items);
+        let message = format!(
+            "{}{}{}{}",
+            "\\logosection{\\faCogs}{",
+            cv_label(self.locale, CvLabel::Skill),
+            "}\n\n",
+            work_items
+        );
         return message;
     }
 
     fn gen_project(&self, cv_main: &CvMainResp) -> String {
         let work_items = get_zheyuye_project_str(&cv_main.projects);
         let message = format!(
-            "{}{}",
-            "\\logosection{\\faWrench}{项目经历}\n\n", work_items
+            "{}{}{}{}",
+            "\\logosection{\\faWrench}{",
+            cv_label(self.locale, CvLabel::Project),
+            "}\n\n",
+            work_items
         );
         return message;
     }
@@ -104,8 +164,11 @@ impl CvRender for ZheyuyeCvGenImpl {
                 }
                 let work_items = get_zheyuye_lang_str(&cv_main.langs);
                 let message = format!(
-                    "{}{}",
-                    "\\logosection{\\faLanguage}{语言技能}\n\n", work_items
+                    "{}{}{}{}",
+                    "\\logosection{\\faLanguage}{",
+                    cv_label(self.locale, CvLabel::Lang),
+                    "}\n\n",
+                    work_items
                 );
                 return message;
             }
@@ -1,3 +1,4 @@
 pub mod render_worker;
 pub mod cv;
-pub mod texhub;
\ No newline at end of file
+pub mod texhub;
+pub mod preview;
\ No newline at end of file
@@ -0,0 +1 @@
+pub mod page_preview;
\ No newline at end of file
@@ -0,0 +1,153 @@
+use crate::common::settings::app_settings::CvSettings;
+use log::{error, warn};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/**
+ * the page image preview options
+ * read from the `cv.preview_*` config
+ */
+pub struct PreviewOptions {
+    pub dpi: u32,
+    /// the thumbnail width in pixel, the height keep the page ratio
+    /// 0 means render in the original size of the dpi
+    pub thumb_width: u32,
+    pub all_pages: bool,
+    /// png or webp
+    pub format: String,
+}
+
+impl PreviewOptions {
+    pub fn from_settings(cv: &CvSettings) -> Self {
+        return PreviewOptions {
+            dpi: cv.preview_dpi,
+            thumb_width: cv.preview_thumb_width,
+            all_pages: cv.preview_all_pages,
+            format: if cv.preview_format == "webp" {
+                cv.preview_format.clone()
+            } else {
+                "png".to_owned()
+            },
+        };
+    }
+}
+
+/**
+ * rasterise the pdf pages to images next to the pdf file
+ * the first page named `{pdf_stem}.{ext}`, others named `{pdf_stem}-{page}.{ext}`
+ * return the generated image paths
+ */
+pub fn render_pdf_previews(
+    pdf_path: &str,
+    options: &PreviewOptions,
+) -> Result<Vec<String>, String> {
+    let pdf = Path::new(pdf_path);
+    if !pdf.exists() {
+        return Err(format!("pdf file not found: {}", pdf_path));
+    }
+    let out_dir = pdf.parent().unwrap_or(Path::new("."));
+    let stem = pdf.file_stem().unwrap().to_string_lossy().to_string();
+    let page_prefix = out_dir.join(format!("{}{}", stem, "-page"));
+    let mut cmd = Command::new("pdftoppm");
+    cmd.arg("-png").arg("-r").arg(options.dpi.to_string());
+    // the scale size take precedence over the dpi in pdftoppm
+    if options.thumb_width > 0 {
+        cmd.arg("-scale-to-x")
+            .arg(options.thumb_width.to_string())
+            .arg("-scale-to-y")
+            .arg("-1");
+    }
+    if !options.all_pages {
+        cmd.arg("-f").arg("1").arg("-l").arg("1");
+    }
+    let output = cmd.arg(pdf_path).arg(&page_prefix).output();
+    match output {
+        Ok(out) => {
+            if !out.status.success() {
+                let err_msg = String::from_utf8_lossy(&out.stderr);
+                error!("rasterise pdf failed: {}, pdf: {}", err_msg, pdf_path);
+                return Err(format!("rasterise pdf failed: {}", err_msg));
+            }
+        }
+        Err(e) => {
+            error!("execute pdftoppm command failed: {}, pdf: {}", e, pdf_path);
+            return Err(format!("execute pdftoppm command failed: {}", e));
+        }
+    }
+    let mut previews = Vec::new();
+    for (index, page_image) in list_page_images(out_dir, &stem).iter().enumerate() {
+        let name = if index == 0 {
+            stem.clone()
+        } else {
+            format!("{}-{}", stem, index + 1)
+        };
+        let png_path = out_dir.join(format!("{}.png", name));
+        if let Err(e) = fs::rename(page_image, &png_path) {
+            error!("rename page image failed: {}, image: {:?}", e, page_image);
+            continue;
+        }
+        if options.format == "webp" {
+            match to_webp(&png_path) {
+                Some(webp_path) => previews.push(webp_path),
+                None => previews.push(png_path.to_string_lossy().to_string()),
+            }
+        } else {
+            previews.push(png_path.to_string_lossy().to_string());
+        }
+    }
+    return Ok(previews);
+}
+
+/**
+ * pdftoppm pad the page number by the page count digits
+ * so sort the `{stem}-page-N.png` by the parsed page number
+ */
+fn list_page_images(out_dir: &Path, stem: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}{}", stem, "-page-");
+    let mut pages: Vec<(u32, PathBuf)> = Vec::new();
+    if let Ok(entries) = fs::read_dir(out_dir) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if !file_name.starts_with(&prefix) || !file_name.ends_with(".png") {
+                continue;
+            }
+            let page_no = file_name[prefix.len()..file_name.len() - 4]
+                .parse::<u32>()
+                .unwrap_or(0);
+            pages.push((page_no, entry.path()));
+        }
+    }
+    pages.sort_by_key(|p| p.0);
+    return pages.into_iter().map(|p| p.1).collect();
+}
+
+fn to_webp(png_path: &Path) -> Option<String> {
+    let webp_path = png_path.with_extension("webp");
+    let output = Command::new("cwebp")
+        .arg("-quiet")
+        .arg("-q")
+        .arg("80")
+        .arg(png_path)
+        .arg("-o")
+        .arg(&webp_path)
+        .output();
+    match output {
+        Ok(out) if out.status.success() => {
+            let _ = fs::remove_file(png_path);
+            return Some(webp_path.to_string_lossy().to_string());
+        }
+        Ok(out) => {
+            warn!(
+                "convert preview to webp failed, fallback to png: {}",
+                String::from_utf8_lossy(&out.stderr)
+            );
+        }
+        Err(e) => {
+            warn!("execute cwebp command failed, fallback to png: {}", e);
+        }
+    }
+    return None;
+}
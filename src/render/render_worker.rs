@@ -1,45 +1,47 @@
 use crate::{
-    controller::tex::tex_controller::update_queue_compile_status,
+    common::{metrics::app_metrics::observe_cv_render, settings::app_settings::app_settings},
     model::{
-        cv::{cv_gen::CvGen, cv_main::CvMainResp},
-        project::{
-            compile_app_params::CompileAppParams, tex_file_compile_status::TeXFileCompileStatus,
+        cv::{cv_gen::CvGen, cv_locale::CvLocale, cv_main::CvMainResp},
+        project::compile_app_params::CompileAppParams,
+        request::cv::{
+            cv_preview_request::CvPreviewRequest, render_handle_request::RenderHandleRequest,
         },
-        request::cv::render_handle_request::RenderHandleRequest,
         response::tex::compile_output::CompileOutput,
         template::cv_template::CvTemplate,
     },
-    rest::client::cv_client::update_gen_result
+    render::{
+        preview::page_preview::{render_pdf_previews, PreviewOptions},
+        texhub::pipeline::pipeline_render_works::compile_deadline,
+    },
+    rest::client::{cv_client::update_gen_result, cv_photo_client::download_cv_photo},
+    util::cv_util::escape_cv_main,
 };
 use chrono::{Datelike, Utc};
 use log::{error, info, warn};
 use rust_wheel::{
     common::util::{
         net::sse_message::SSEMessage,
-        rd_file_util::{create_folder_not_exists, get_filename_without_ext, join_paths},
+        rd_file_util::{create_folder_not_exists, get_filename_without_ext},
     },
-    config::app::app_conf_reader::get_app_config,
-    texhub::{proj::compile_result::CompileResult, project::get_proj_path},
 };
 use sha256::try_digest;
 use std::{
-    env,
-    fs::{self, OpenOptions},
-    io::{self, BufRead, BufReader, Error, Write},
+    env, fs,
+    io::{self, BufRead, BufReader},
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::{Command, Output, Stdio},
     sync::{Arc, Mutex},
     thread,
     time::Duration,
 };
-use tokio::{sync::mpsc::UnboundedSender, task};
+use tokio::{sync::mpsc::UnboundedSender, task, time::timeout};
 use uuid::Uuid;
 
 use super::cv::{
     dyweb::dyweb_handler::DywebHandler, handler::template_handler::TemplateHandler,
     hijiangtao::hijiangtao_handler::HijiangtaoHandler, moderncv::moderncv_handler::ModerncvHandler,
-    moderncv1::moderncv_handler1::ModerncvHandler1, weitian::weitian_handler::WeitianHandler,
-    zheyuye::zheyuye_handler::ZheyuyeHandler,
+    moderncv1::moderncv_handler1::ModerncvHandler1, rodrigo::rodrigo_handler::RodrigoHandler,
+    weitian::weitian_handler::WeitianHandler, zheyuye::zheyuye_handler::ZheyuyeHandler,
 };
 
 pub async fn render_texhub_project_sse(
@@ -93,78 +95,6 @@ pub async fn render_texhub_project_sse(
     }
 }
 
-pub fn render_texhub_project_mq(params: &CompileAppParams) -> Option<CompileResult> {
-    let base_texhub_dir = get_app_config("cv.texhub_proj_base_dir");
-    let proj_comp_dir = get_proj_path(&base_texhub_dir, params.proj_created_time);
-    let current_dir = join_paths(&[proj_comp_dir.clone(), params.project_id.clone()]);
-    // let compile_out_path = format!("{}/{}", current_dir, params.version_no);
-    // create_folder_not_exists(&compile_out_path);
-    // we remove the -output-directory because:
-    // 1. facing this issue: https://tex.stackexchange.com/questions/697033/is-it-possible-to-auto-create-dist-folder-when-not-exists-using-xelatex-compile
-    // 2. maybe output-directory have some compatible issue with latex compile engine
-    let cmd = Command::new("xelatex")
-        .arg("-synctex=1")
-        //.arg("-output-directory")
-        //.arg(compile_out_path.clone())
-        .arg(params.file_path.clone())
-        .current_dir(&current_dir)
-        .output();
-    if let Err(e) = cmd {
-        error!("compile tex file failed: {}, parmas: {:?}", e, params);
-        return Some(CompileResult::Failure);
-    }
-    let log_file_name = format!("{}/{}", current_dir, params.log_file_name);
-    let file: Result<std::fs::File, Error> = OpenOptions::new().append(true).open(&log_file_name);
-    if let Err(e) = file {
-        error!(
-            "open log file failed: {}, parmas: {:?}, log full path: {}",
-            e, params, &log_file_name
-        );
-        return Some(CompileResult::Failure);
-    }
-    // flush newest compile status before the disk log end flag
-    // avoid query to compiling status when send end flag to the client
-    // reqwest requires a multi-threaded runtime to properly handle HTTP requests
-    let rt = tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
-        .build()
-        .unwrap();
-    rt.block_on(update_queue_compile_status(
-        params.to_owned(),
-        TeXFileCompileStatus::Compiling,
-    ));
-    let mut naked_file = file.unwrap();
-    let wr = naked_file.write_all("\n====END====\n".as_bytes());
-    if let Err(e) = wr {
-        error!("write log file failed: {}, parmas: {:?}", e, params);
-    }
-    let sync_result = naked_file.sync_all();
-    if let Err(e) = sync_result {
-        error!("sync log file failed: {}, parmas: {:?}", e, params);
-    }
-    let output = cmd.unwrap();
-    let status = output.status;
-    match status.code() {
-        Some(code) => {
-            if code != 0 {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                error!(
-                    "Process exited with status code: {}. stderr: {}, stdout: {}",
-                    code, stderr, stdout
-                );
-            }
-        }
-        None => {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            warn!("Process terminated by signal. stderr: {}, stdout: {}", stderr, stdout);
-        }
-    }
-    return Some(CompileResult::Success);
-}
-
-
 pub fn do_msg_send(
     line: &String,
     tx: Arc<std::sync::Mutex<UnboundedSender<String>>>,
@@ -233,36 +163,41 @@ pub async fn render_impl(cv_gen: &CvGen, cv_tpl: CvTemplate, cv_main: CvMainResp
     let out_path = get_dist_path(&relative_path);
     let result = fs::create_dir_all(&out_path);
     let file_path = format!("{}{}", out_path, "/modern.tex");
-    let handler = ModerncvHandler {
-        next: Some(Box::new(ZheyuyeHandler {
-            next: Some(Box::new(DywebHandler {
-                next: Some(Box::new(WeitianHandler {
-                    next: Some(Box::new(HijiangtaoHandler {
-                        next: Some(Box::new(ModerncvHandler1 {})),
-                    })),
-                })),
-            })),
-        })),
-    };
+    let handler = get_cv_handler();
+    let photo_path = download_cv_photo(&cv_main.photo, &out_path).await;
+    let template_code = cv_tpl.template_code.unwrap();
+    let cv_main = escape_cv_main(&cv_main);
     let req = RenderHandleRequest {
-        template_code: cv_tpl.template_code.unwrap(),
+        template_code: template_code.clone(),
         file_path: &file_path,
         cv_main: cv_main.clone(),
+        locale: CvLocale::from(cv_main.locale.as_deref()),
+        photo_path: photo_path,
     };
-    handler.handle_request(req, &cv_main).unwrap();
+    if let Err(e) = handler.handle_request(req, &cv_main) {
+        error!("render cv failed: {}, template: {}", e, template_code);
+        observe_cv_render(&template_code, false);
+        return;
+    }
     if result.is_ok() {
-        let output = Command::new("xelatex")
-            .arg("-output-directory")
-            .arg(&out_path)
-            .arg(&file_path)
-            .output();
+        let output = run_cv_xelatex(&out_path, &file_path).await;
         match output {
             Ok(succ_output) => {
                 if succ_output.status.success() {
                     let file_name = copy_file_to_server(&file_path, &relative_path, "pdf").await;
                     let tex_file_name =
                         copy_file_to_server(&file_path, &relative_path, "tex").await;
-                    update_gen_result(cv_gen.id, &file_name, &tex_file_name).await;
+                    let preview_file_name =
+                        copy_preview_to_server(&file_path, &relative_path).await;
+                    update_gen_result(
+                        cv_gen.id,
+                        cv_gen.user_id,
+                        &file_name,
+                        &tex_file_name,
+                        preview_file_name,
+                    )
+                    .await;
+                    observe_cv_render(&template_code, true);
                     info!("Compilation successful!");
                 } else {
                     let err_msg = String::from_utf8_lossy(&succ_output.stderr);
@@ -271,15 +206,254 @@ pub async fn render_impl(cv_gen: &CvGen, cv_tpl: CvTemplate, cv_main: CvMainResp
                         "Compilation failed: std error: {}, std out: {}",
                         err_msg, out_msg
                     );
+                    observe_cv_render(&template_code, false);
                 }
             }
             Err(e) => {
                 error!("execute xelatex command failed, {}", e);
+                observe_cv_render(&template_code, false);
             }
         }
     }
 }
 
+/// the template codes handled by `get_cv_handler`
+const CV_TEMPLATE_CODES: [&str; 6] = [
+    "moderncv",
+    "zheyuye",
+    "dyweb",
+    "weitian",
+    "hijiangtao",
+    "rodrigo",
+];
+
+/**
+ * the unknown template was the invalid input, check it before downloading the photo and rendering
+ */
+fn check_template_code(template_code: &str) -> io::Result<()> {
+    if CV_TEMPLATE_CODES.contains(&template_code) {
+        return Ok(());
+    }
+    return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("unknown cv template: {}", template_code),
+    ));
+}
+
+fn get_cv_handler() -> ModerncvHandler {
+    return ModerncvHandler {
+        next: Some(Box::new(ZheyuyeHandler {
+            next: Some(Box::new(DywebHandler {
+                next: Some(Box::new(WeitianHandler {
+                    next: Some(Box::new(HijiangtaoHandler {
+                        next: Some(Box::new(RodrigoHandler {
+                            next: Some(Box::new(ModerncvHandler1 {})),
+                        })),
+                    })),
+                })),
+            })),
+        })),
+    };
+}
+
+/**
+ * render the cv in a temp dir and return the pdf bytes(or the tex source)
+ * the temp dir was removed after the render whether success or not
+ */
+pub async fn render_cv_preview(params: CvPreviewRequest, tex_only: bool) -> io::Result<Vec<u8>> {
+    check_template_code(&params.template_code)?;
+    let preview_dir = env::temp_dir().join(format!(
+        "{}{}",
+        "cv-preview-",
+        Uuid::new_v4().to_string().replace("-", "")
+    ));
+    if let Err(e) = fs::create_dir_all(&preview_dir) {
+        error!(
+            "create cv preview dir failed: {}, dir: {:?}",
+            e, preview_dir
+        );
+        return Err(e);
+    }
+    let out_path = preview_dir.to_string_lossy().to_string();
+    let photo_path = if tex_only {
+        None
+    } else {
+        download_cv_photo(&params.cv_main.photo, &out_path).await
+    };
+    let template_code = params.template_code.clone();
+    let result = render_cv_preview_impl(params, out_path, photo_path, tex_only).await;
+    if !tex_only {
+        observe_cv_render(&template_code, result.is_ok());
+    }
+    if let Err(e) = fs::remove_dir_all(&preview_dir) {
+        warn!(
+            "remove cv preview dir failed: {}, dir: {:?}",
+            e, preview_dir
+        );
+    }
+    return result;
+}
+
+async fn render_cv_preview_impl(
+    params: CvPreviewRequest,
+    out_path: String,
+    photo_path: Option<String>,
+    tex_only: bool,
+) -> io::Result<Vec<u8>> {
+    let file_path = format!("{}{}", out_path, "/modern.tex");
+    let tex_path = file_path.clone();
+    let template_code = params.template_code.clone();
+    task::spawn_blocking(move || {
+        let cv_main = escape_cv_main(&params.cv_main);
+        let req = RenderHandleRequest {
+            template_code: params.template_code.clone(),
+            file_path: &tex_path,
+            cv_main: cv_main.clone(),
+            locale: CvLocale::from(cv_main.locale.as_deref()),
+            photo_path: photo_path,
+        };
+        // the unknown template treat as the invalid input
+        return get_cv_handler()
+            .handle_request(req, &cv_main)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e));
+    })
+    .await
+    .unwrap_or_else(|e| Err(io::Error::new(io::ErrorKind::Other, e)))?;
+    if tex_only {
+        return fs::read(&file_path);
+    }
+    match run_cv_xelatex(&out_path, &file_path).await {
+        Ok(out) => {
+            if !out.status.success() {
+                let out_msg = String::from_utf8_lossy(&out.stdout);
+                error!(
+                    "cv preview compile failed, template: {}, std out: {}",
+                    template_code, out_msg
+                );
+                return Err(io::Error::new(io::ErrorKind::Other, "compile cv failed"));
+            }
+            let pdf_path = PathBuf::from(&file_path).with_extension("pdf");
+            return fs::read(pdf_path);
+        }
+        Err(e) => {
+            error!("execute xelatex command failed, {}", e);
+            return Err(e);
+        }
+    }
+}
+
+/**
+ * the cv was the user input, compile it without the shell escape and only read the files in the cv dir
+ * the xelatex was killed when the compile deadline was reached
+ */
+async fn run_cv_xelatex(out_path: &str, file_path: &str) -> io::Result<Output> {
+    let output = tokio::process::Command::new("xelatex")
+        .arg("-no-shell-escape")
+        .arg("-interaction=nonstopmode")
+        .arg("-output-directory")
+        .arg(out_path)
+        .arg(file_path)
+        .current_dir(out_path)
+        // the paranoid mode only allow the absolute path under TEXMFOUTPUT, e.g. the photo
+        .env("TEXMFOUTPUT", out_path)
+        .env("openin_any", "p")
+        .env("openout_any", "p")
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let deadline = compile_deadline();
+    match timeout(deadline, output).await {
+        Ok(output) => return output,
+        Err(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!(
+                    "xelatex was killed after {:?}, file: {}",
+                    deadline, file_path
+                ),
+            ));
+        }
+    }
+}
+
+/**
+ * render the first page of the cv pdf and copy the image to the server
+ */
+async fn copy_preview_to_server(tex_file_path: &str, out_relative_path: &str) -> Option<String> {
+    let pdf_path = PathBuf::from(tex_file_path).with_extension("pdf");
+    let options = PreviewOptions {
+        all_pages: false,
+        ..PreviewOptions::from_settings(&app_settings().cv)
+    };
+    match render_pdf_previews(&pdf_path.to_string_lossy(), &options) {
+        Ok(previews) => {
+            let first_page = previews.first()?;
+            let ext = Path::new(first_page)
+                .extension()?
+                .to_string_lossy()
+                .to_string();
+            let file_name = copy_file_to_server(tex_file_path, out_relative_path, &ext).await;
+            if file_name.is_empty() {
+                return None;
+            }
+            return Some(file_name);
+        }
+        Err(e) => {
+            warn!(
+                "render cv preview image failed: {}, file: {}",
+                e, tex_file_path
+            );
+            return None;
+        }
+    }
+}
+
+/**
+ * regenerate the template gallery preview from the sample cv
+ * return the preview image name on the server
+ */
+pub async fn render_template_preview(
+    template_code: String,
+    cv_main: CvMainResp,
+) -> io::Result<String> {
+    // the template code was a part of the server path
+    check_template_code(&template_code)?;
+    let preview_dir = env::temp_dir().join(format!(
+        "{}{}",
+        "cv-tpl-preview-",
+        Uuid::new_v4().to_string().replace("-", "")
+    ));
+    fs::create_dir_all(&preview_dir)?;
+    let out_path = preview_dir.to_string_lossy().to_string();
+    let photo_path = download_cv_photo(&cv_main.photo, &out_path).await;
+    let params = CvPreviewRequest {
+        template_code: template_code.clone(),
+        cv_main: cv_main,
+    };
+    let result = render_cv_preview_impl(params, out_path.clone(), photo_path, false).await;
+    let preview = match result {
+        Ok(_) => {
+            let file_path = format!("{}{}", out_path, "/modern.tex");
+            let relative_path = format!("{}{}", "/tpl/", template_code);
+            copy_preview_to_server(&file_path, &relative_path).await
+        }
+        Err(e) => {
+            let _ = fs::remove_dir_all(&preview_dir);
+            return Err(e);
+        }
+    };
+    if let Err(e) = fs::remove_dir_all(&preview_dir) {
+        warn!(
+            "remove template preview dir failed: {}, dir: {:?}",
+            e, preview_dir
+        );
+    }
+    return preview.ok_or(io::Error::new(
+        io::ErrorKind::Other,
+        "generate template preview failed",
+    ));
+}
+
 async fn copy_file_to_server(
     input_file_path: &str,
     out_relative_path: &str,
@@ -290,7 +464,13 @@ async fn copy_file_to_server(
     let new_file_path = new_path.as_path().to_str().unwrap();
     let file_info = Path::new(new_file_path);
     let file_sha = try_digest(file_info).unwrap();
-    let ssh_pwd = env::var("CV_REMOTE_SSH_PWD").unwrap();
+    let ssh_pwd = match &app_settings().cv_remote_ssh_pwd {
+        Some(pwd) => pwd.clone(),
+        None => {
+            error!("CV_REMOTE_SSH_PWD is missing, file path: {}", input_file_path);
+            return "".to_string();
+        }
+    };
     let output = Command::new("sshpass")
         .arg("-p")
         .arg(ssh_pwd)
@@ -337,7 +517,7 @@ async fn copy_file_to_server(
 }
 
 fn get_dist_path(relative_path: &String) -> String {
-    let base_cv_dir = get_app_config("cv.cv_compile_base_dir");
+    let base_cv_dir = app_settings().cv.cv_compile_base_dir.clone();
     let full_path = format!("{}{}{}", base_cv_dir, "/", relative_path);
     return full_path;
 }
@@ -351,3 +531,40 @@ fn get_relative_path(user_id: i64, tpl_id: i64, cv_id: i64) -> String {
     let relative_path = format!("{}{}{}{}", "/", time_path, "/", user_path);
     return relative_path;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_template_was_invalid_input() {
+        for template_code in CV_TEMPLATE_CODES {
+            assert!(check_template_code(template_code).is_ok());
+        }
+        for template_code in ["", "unknown", "../moderncv"] {
+            let e = check_template_code(template_code).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn every_template_code_was_handled() {
+        let cv_main = CvMainResp::default();
+        for template_code in CV_TEMPLATE_CODES {
+            let file_path = env::temp_dir()
+                .join(format!("{}-{}.tex", template_code, Uuid::new_v4()))
+                .to_string_lossy()
+                .to_string();
+            let req = RenderHandleRequest {
+                template_code: template_code.to_owned(),
+                file_path: &file_path,
+                cv_main: cv_main.clone(),
+                locale: CvLocale::from(None),
+                photo_path: None,
+            };
+            let result = get_cv_handler().handle_request(req, &cv_main);
+            let _ = fs::remove_file(&file_path);
+            assert!(result.is_ok(), "{} was not handled", template_code);
+        }
+    }
+}
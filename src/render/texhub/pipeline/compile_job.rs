@@ -0,0 +1,210 @@
+use super::compile_log_sink::CompileLogSink;
+use crate::{
+    common::logging::job_log_context::set_log_stage,
+    model::project::{compile_app_params::CompileAppParams, compile_job_state::CompileJobState},
+    rest::client::texhub_client::texhub_client,
+    service::{
+        compile_cancel_service::CompileCancelSignal, compile_requeue_service::requeue_compile_job,
+    },
+    task::app_shutdown::REQUEUE_REASON,
+};
+use log::{error, info, warn};
+use rust_wheel::texhub::proj::compile_result::CompileResult;
+use tokio::runtime::Handle;
+
+/**
+ * one project compile job, every state change was validated and then published
+ * to the live log stream and the texhub compile queue
+ */
+pub struct CompileJob<'a> {
+    params: &'a CompileAppParams,
+    state: CompileJobState,
+    sink: CompileLogSink,
+    cancel: CompileCancelSignal,
+    /// the job was put back to the stream, this worker did not report the result
+    requeued: bool,
+}
+
+impl<'a> CompileJob<'a> {
+    pub fn new(params: &'a CompileAppParams, sink: CompileLogSink) -> Self {
+        return CompileJob {
+            params: params,
+            state: CompileJobState::Queued,
+            sink: sink,
+            cancel: CompileCancelSignal::new(&params.project_id, params.qid),
+            requeued: false,
+        };
+    }
+
+    pub fn state(&self) -> CompileJobState {
+        return self.state;
+    }
+
+    pub fn sink(&mut self) -> &mut CompileLogSink {
+        return &mut self.sink;
+    }
+
+    /// the sink and the cancel signal for the running engine
+    pub fn engine_io(&mut self) -> (&mut CompileLogSink, &mut CompileCancelSignal) {
+        return (&mut self.sink, &mut self.cancel);
+    }
+
+    /**
+     * move the job to cancelled when the cancel signal was received
+     * check it between the steps, the running engine check it by itself
+     */
+    pub async fn cancel_requested(&mut self) -> bool {
+        let reason = match self.cancel.cancelled().await {
+            Some(r) => r,
+            None => return false,
+        };
+        info!("compile job {}, qid: {}", reason, self.params.qid);
+        self.stop_cancelled(&reason).await;
+        return true;
+    }
+
+    /**
+     * stop the job by the cancel reason, the job interrupted by the shutdown was requeued
+     */
+    pub async fn stop_cancelled(&mut self, reason: &str) {
+        if reason == REQUEUE_REASON {
+            self.requeue().await;
+            return;
+        }
+        self.sink.line(&format!("Compilation {}.", reason));
+        self.transition(CompileJobState::Cancelled).await;
+    }
+
+    /**
+     * the worker was shutting down, put the job back to the stream and end the log stream
+     * the subscriber sees the end event and follow the job on the next worker
+     */
+    async fn requeue(&mut self) {
+        if self.state.is_terminal() || self.requeued {
+            return;
+        }
+        self.requeued = true;
+        self.sink
+            .line("Compilation interrupted by the worker shutdown, the job was requeued.");
+        self.sink.end(REQUEUE_REASON);
+        // clear before the requeue, the next worker owns the signal keys of the qid
+        self.cancel.clear().await;
+        if !requeue_compile_job(self.params).await {
+            // could not put it back, report the failure so the job did not hang
+            self.requeued = false;
+            self.transition(CompileJobState::FailedWithErrors).await;
+        }
+    }
+
+    /**
+     * move the job to the next state, the invalid transition was ignored and return false
+     */
+    pub async fn transition(&mut self, next: CompileJobState) -> bool {
+        if !self.apply(next) {
+            return false;
+        }
+        let reported = texhub_client()
+            .update_job_state(self.params.user_id, self.params.qid, &next)
+            .await;
+        if let Err(e) = reported {
+            error!(
+                "Failed to update compile job state: {}, state: {}, params: {:?}",
+                e,
+                next.name(),
+                self.params
+            );
+        }
+        if self.finish_if_terminal() {
+            self.cancel.clear().await;
+        }
+        return true;
+    }
+
+    /// validate and publish the state to the log stream
+    fn apply(&mut self, next: CompileJobState) -> bool {
+        if self.requeued {
+            return false;
+        }
+        if !self.state.can_transition_to(&next) {
+            warn!(
+                "invalid compile job transition {:?} -> {:?}, qid: {}",
+                self.state, next, self.params.qid
+            );
+            return false;
+        }
+        info!(
+            "compile job transition {:?} -> {:?}, qid: {}",
+            self.state, next, self.params.qid
+        );
+        self.state = next;
+        set_log_stage(next.name());
+        let pass = match next {
+            CompileJobState::Compiling { pass } => Some(pass),
+            _ => None,
+        };
+        self.sink.status(next.name(), pass);
+        return true;
+    }
+
+    /// end the log stream when the job reached the terminal state, the caller clears the cancel signal
+    fn finish_if_terminal(&mut self) -> bool {
+        if !self.state.is_terminal() {
+            return false;
+        }
+        // the end event keeps the coarse result, the detail was sent as the status above
+        let result = match self.state.compile_result() {
+            CompileResult::Success => "success",
+            _ => "failure",
+        };
+        self.sink.end(result);
+        return true;
+    }
+}
+
+/**
+ * fail the job before it started, the texhub status and the log stream got the end as usual
+ */
+pub async fn fail_unstarted_job(params: &CompileAppParams, message: &str) -> CompileResult {
+    let mut job = CompileJob::new(params, CompileLogSink::open(params, None));
+    job.sink().line(message);
+    job.transition(CompileJobState::FailedWithErrors).await;
+    return job.state().compile_result();
+}
+
+/**
+ * the job future was dropped or panicked before the terminal state
+ * end the log stream now and report the failure to texhub in the background
+ */
+impl<'a> Drop for CompileJob<'a> {
+    fn drop(&mut self) {
+        if self.state.is_terminal() || self.requeued {
+            return;
+        }
+        warn!(
+            "compile job dropped in state {:?}, qid: {}",
+            self.state, self.params.qid
+        );
+        let next = CompileJobState::FailedWithErrors;
+        if !self.apply(next) {
+            return;
+        }
+        self.finish_if_terminal();
+        let qid = self.params.qid;
+        let user_id = self.params.user_id;
+        let cancel = self.cancel.clone();
+        match Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    cancel.clear().await;
+                    if let Err(e) = texhub_client().update_job_state(user_id, qid, &next).await {
+                        error!(
+                            "Failed to report the dropped compile job: {}, qid: {}",
+                            e, qid
+                        );
+                    }
+                });
+            }
+            Err(e) => error!("report the dropped compile job failed: {}, qid: {}", e, qid),
+        }
+    }
+}
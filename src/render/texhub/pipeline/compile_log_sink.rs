@@ -0,0 +1,478 @@
+use super::pipeline_render_works::{create_consumer_group, del_redis_stream};
+use crate::{
+    common::cache::redis_async::shared_redis,
+    model::project::compile_app_params::CompileAppParams,
+    service::{
+        compile_cancel_service::CompileCancelSignal,
+        compile_log_service::{compile_log_stream_key, COMPILE_LOG_END_MARKER},
+    },
+};
+use log::{error, warn};
+use redis::aio::ConnectionManager;
+use std::{
+    collections::VecDeque,
+    io,
+    process::{ExitStatus, Stdio},
+    time::{Duration, Instant},
+};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader},
+    process::{Child, Command},
+    runtime::Handle,
+    sync::mpsc::{self, error::TrySendError, Receiver, Sender},
+    time::timeout,
+};
+
+/// keep the last output lines for the compile error summary
+const OUTPUT_TAIL_LINES: usize = 200;
+/// wake up to check the cancel signal when the engine prints nothing
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// the queued events of the sink writer, the engine output waits when it was full
+const SINK_CHANNEL_CAPACITY: usize = 1024;
+/// the lines read from the engine pipes, the reader stops reading the pipe when it was full
+const PIPE_CHANNEL_CAPACITY: usize = 256;
+
+/**
+ * the compile log destination, write every engine output line to the log file and the redis stream
+ * the end event is guaranteed to be sent once, even the pipeline returns early or panics
+ * the writes were queued to the writer task in order, the engine output waits when the writer fell behind
+ */
+pub struct CompileLogSink {
+    stream_key: String,
+    tx: Sender<SinkEvent>,
+    ended: bool,
+    legacy_end_marker: bool,
+    /// the lines dropped by `line` when the queue was full
+    dropped: usize,
+}
+
+enum SinkEvent {
+    /// the output line, written to the log file and the stream
+    Line(String),
+    /// the stream only fields
+    Fields(Vec<(&'static str, String)>),
+    End {
+        result: String,
+        legacy_end_marker: bool,
+    },
+}
+
+/**
+ * own the log file and the redis connection of one sink
+ */
+struct SinkWriter {
+    stream_key: String,
+    log_file: Option<File>,
+    con: Option<ConnectionManager>,
+}
+
+pub struct EngineOutcome {
+    /// None when the engine was killed by the deadline or the cancel signal
+    pub status: Option<ExitStatus>,
+    /// the cancel reason when the engine was killed by the cancel signal
+    pub cancelled: Option<String>,
+    pub output_tail: Vec<String>,
+    pub warnings: usize,
+    /// the engine asked for another pass to resolve the cross references
+    pub rerun_requested: bool,
+}
+
+impl CompileLogSink {
+    /**
+     * the log file is None when the path is the engine's own `{jobname}.log`
+     * the engine writes the full log there and would overwrite our content
+     */
+    pub fn open(params: &CompileAppParams, log_file_path: Option<&str>) -> Self {
+        let stream_key = compile_log_stream_key(&params.project_id, params.qid);
+        let (tx, rx) = mpsc::channel::<SinkEvent>(SINK_CHANNEL_CAPACITY);
+        tokio::spawn(run_sink_writer(
+            params.clone(),
+            stream_key.clone(),
+            log_file_path.map(|p| p.to_owned()),
+            rx,
+        ));
+        return CompileLogSink {
+            stream_key: stream_key,
+            tx: tx,
+            ended: false,
+            legacy_end_marker: false,
+            dropped: 0,
+        };
+    }
+
+    /**
+     * the line was dropped when the queue was full, the engine output use `write_line` to wait instead
+     */
+    pub fn line(&mut self, line: &str) {
+        self.send(SinkEvent::Line(line.to_owned()));
+    }
+
+    /**
+     * wait for the writer when the queue was full, the backpressure reach the engine through the pipe
+     */
+    pub async fn write_line(&mut self, line: &str) {
+        if self
+            .tx
+            .send(SinkEvent::Line(line.to_owned()))
+            .await
+            .is_err()
+        {
+            error!("compile log writer stopped, stream: {}", self.stream_key);
+        }
+    }
+
+    /**
+     * also write the legacy end marker to the log file, for the readers tailing the file
+     */
+    pub fn set_legacy_end_marker(&mut self, enabled: bool) {
+        self.legacy_end_marker = enabled;
+    }
+
+    pub fn status(&mut self, status: &str, pass: Option<u32>) {
+        let mut fields = vec![("status", status.to_owned())];
+        if let Some(p) = pass {
+            fields.push(("pass", p.to_string()));
+        }
+        self.send(SinkEvent::Fields(fields));
+    }
+
+    /**
+     * the termination event of the compile, the subscriber stop reading after it
+     */
+    pub fn end(&mut self, result: &str) {
+        if self.ended {
+            return;
+        }
+        self.ended = true;
+        if self.dropped > 0 {
+            warn!(
+                "{} compile log lines were dropped, stream: {}",
+                self.dropped, self.stream_key
+            );
+        }
+        let event = SinkEvent::End {
+            result: result.to_owned(),
+            legacy_end_marker: self.legacy_end_marker,
+        };
+        match self.tx.try_send(event) {
+            Ok(_) => {}
+            // the end event must not be lost, wait for the writer in the background
+            Err(TrySendError::Full(event)) => match Handle::try_current() {
+                Ok(handle) => {
+                    let tx = self.tx.clone();
+                    handle.spawn(async move {
+                        let _ = tx.send(event).await;
+                    });
+                }
+                Err(_) => error!("compile log end event lost, stream: {}", self.stream_key),
+            },
+            Err(TrySendError::Closed(_)) => {
+                error!("compile log writer stopped, stream: {}", self.stream_key)
+            }
+        }
+    }
+
+    fn send(&mut self, event: SinkEvent) {
+        match self.tx.try_send(event) {
+            Ok(_) => {}
+            Err(TrySendError::Full(_)) => self.dropped += 1,
+            Err(TrySendError::Closed(_)) => {
+                error!("compile log writer stopped, stream: {}", self.stream_key)
+            }
+        }
+    }
+}
+
+impl Drop for CompileLogSink {
+    fn drop(&mut self) {
+        if !self.ended {
+            warn!(
+                "compile log closed without end event, stream: {}",
+                self.stream_key
+            );
+            self.end("failure");
+        }
+    }
+}
+
+/**
+ * write the queued events in order until the sink was dropped
+ */
+async fn run_sink_writer(
+    params: CompileAppParams,
+    stream_key: String,
+    log_file_path: Option<String>,
+    mut rx: Receiver<SinkEvent>,
+) {
+    let mut writer = SinkWriter::open(&params, stream_key, log_file_path).await;
+    while let Some(event) = rx.recv().await {
+        match event {
+            SinkEvent::Line(line) => {
+                writer.write_file(&line).await;
+                writer.xadd(&[("msg", line)]).await;
+            }
+            SinkEvent::Fields(fields) => writer.xadd(&fields).await,
+            SinkEvent::End {
+                result,
+                legacy_end_marker,
+            } => {
+                if legacy_end_marker {
+                    writer.write_file(COMPILE_LOG_END_MARKER).await;
+                }
+                writer.sync_file().await;
+                writer.xadd(&[("end", result)]).await;
+            }
+        }
+    }
+}
+
+impl SinkWriter {
+    async fn open(
+        params: &CompileAppParams,
+        stream_key: String,
+        log_file_path: Option<String>,
+    ) -> Self {
+        let mut log_file = None;
+        if let Some(path) = log_file_path {
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&path)
+                .await;
+            match file {
+                Ok(f) => log_file = Some(f),
+                Err(e) => warn!("open compile log file failed: {}, path: {}", e, path),
+            }
+        }
+        let mut con = match shared_redis().await {
+            Ok(c) => Some(c),
+            Err(e) => {
+                error!("{}. Logging locally.", e);
+                None
+            }
+        };
+        if let Some(c) = con.as_mut() {
+            del_redis_stream(params, c).await;
+            create_consumer_group(params, c).await;
+        }
+        return SinkWriter {
+            stream_key: stream_key,
+            log_file: log_file,
+            con: con,
+        };
+    }
+
+    async fn write_file(&mut self, line: &str) {
+        if let Some(file) = self.log_file.as_mut() {
+            if let Err(e) = file.write_all(format!("{}\n", line).as_bytes()).await {
+                error!("write compile log file failed: {}", e);
+            }
+        }
+    }
+
+    async fn sync_file(&mut self) {
+        if let Some(file) = self.log_file.as_mut() {
+            let _ = file.flush().await;
+            let _ = file.sync_all().await;
+        }
+    }
+
+    async fn xadd(&mut self, fields: &[(&str, String)]) {
+        let con = match self.con.as_mut() {
+            Some(c) => c,
+            None => return,
+        };
+        let res: redis::RedisResult<String> = redis::cmd("XADD")
+            .arg(&self.stream_key)
+            .arg("MAXLEN")
+            .arg("~")
+            .arg(5000)
+            .arg("*")
+            .arg(fields)
+            .query_async(con)
+            .await;
+        if let Err(e) = res {
+            error!(
+                "Failed to XADD compile log to redis stream {}: {}. fields: {:?}",
+                self.stream_key, e, fields
+            );
+        }
+    }
+}
+
+/**
+ * run the tex engine and stream the stdout/stderr lines into the sink
+ * the engine was killed when it runs beyond the deadline or the job was cancelled,
+ * and when the compile future was dropped
+ */
+pub async fn run_engine_streaming(
+    mut cmd: Command,
+    sink: &mut CompileLogSink,
+    cancel: &mut CompileCancelSignal,
+    deadline: Duration,
+) -> io::Result<EngineOutcome> {
+    let started = Instant::now();
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let (tx, mut rx) = mpsc::channel::<String>(PIPE_CHANNEL_CAPACITY);
+    spawn_pipe_reader(child.stdout.take(), tx.clone());
+    spawn_pipe_reader(child.stderr.take(), tx);
+    let mut output_tail: VecDeque<String> = VecDeque::new();
+    let mut warnings: usize = 0;
+    let mut rerun_requested = false;
+    loop {
+        let remaining = match deadline.checked_sub(started.elapsed()) {
+            Some(r) => r,
+            None => return kill_engine(child, sink, output_tail, warnings, None).await,
+        };
+        if let Some(reason) = cancel.cancelled().await {
+            return kill_engine(child, sink, output_tail, warnings, Some(reason)).await;
+        }
+        match timeout(remaining.min(CANCEL_CHECK_INTERVAL), rx.recv()).await {
+            Ok(Some(line)) => {
+                // the writer stuck on redis must not hold the engine beyond the deadline
+                if timeout(remaining, sink.write_line(&line)).await.is_err() {
+                    return kill_engine(child, sink, output_tail, warnings, None).await;
+                }
+                if is_engine_warning(&line) {
+                    warnings += 1;
+                }
+                if line.contains("Rerun to get") {
+                    rerun_requested = true;
+                }
+                if output_tail.len() >= OUTPUT_TAIL_LINES {
+                    output_tail.pop_front();
+                }
+                output_tail.push_back(line);
+            }
+            // both pipes closed, the engine is exiting
+            Ok(None) => break,
+            // check the deadline and the cancel signal in the next round
+            Err(_) => continue,
+        }
+    }
+    loop {
+        let remaining = match deadline.checked_sub(started.elapsed()) {
+            Some(r) => r,
+            None => return kill_engine(child, sink, output_tail, warnings, None).await,
+        };
+        if let Some(reason) = cancel.cancelled().await {
+            return kill_engine(child, sink, output_tail, warnings, Some(reason)).await;
+        }
+        if let Ok(status) = timeout(remaining.min(CANCEL_CHECK_INTERVAL), child.wait()).await {
+            return Ok(EngineOutcome {
+                status: Some(status?),
+                cancelled: None,
+                output_tail: output_tail.into(),
+                warnings: warnings,
+                rerun_requested: rerun_requested,
+            });
+        }
+    }
+}
+
+fn spawn_pipe_reader<R: AsyncRead + Unpin + Send + 'static>(pipe: Option<R>, tx: Sender<String>) {
+    let pipe = match pipe {
+        Some(p) => p,
+        None => return,
+    };
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(pipe);
+        let mut buf: Vec<u8> = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf).await {
+                Ok(0) => break,
+                Ok(_) => {
+                    // the tex engine output is not always valid utf-8
+                    let line = String::from_utf8_lossy(&buf).trim_end().to_owned();
+                    if tx.send(line).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    warn!("read engine output failed: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+async fn kill_engine(
+    mut child: Child,
+    sink: &mut CompileLogSink,
+    output_tail: VecDeque<String>,
+    warnings: usize,
+    cancelled: Option<String>,
+) -> io::Result<EngineOutcome> {
+    let pid = child.id().unwrap_or_default();
+    match &cancelled {
+        Some(reason) => warn!("tex engine {}, pid: {}", reason, pid),
+        None => warn!("tex engine exceeded the deadline, pid: {}", pid),
+    }
+    // kill and wait for the exit
+    if let Err(e) = child.kill().await {
+        error!("kill tex engine failed: {}, pid: {}", e, pid);
+    }
+    match &cancelled {
+        Some(reason) => sink.line(&format!(
+            "Compilation {}, the engine was terminated.",
+            reason
+        )),
+        None => sink.line("Compilation timed out, the engine was terminated."),
+    }
+    return Ok(EngineOutcome {
+        status: None,
+        cancelled: cancelled,
+        output_tail: output_tail.into(),
+        warnings: warnings,
+        rerun_requested: false,
+    });
+}
+
+/**
+ * the latex, class and package warnings, the overfull and underfull boxes were counted too
+ * like the compile log diagnostics
+ */
+fn is_engine_warning(line: &str) -> bool {
+    if line.starts_with("Overfull") || line.starts_with("Underfull") {
+        return true;
+    }
+    let is_source =
+        line.starts_with("LaTeX ") || line.starts_with("Package ") || line.starts_with("Class ");
+    return is_source && line.contains(" Warning:");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_engine_warnings_were_counted() {
+        for line in [
+            "LaTeX Warning: Reference `fig:1' on page 1 undefined on input line 12.",
+            "LaTeX Font Warning: Font shape `OT1/cmr/bx/sc' undefined",
+            "Package hyperref Warning: Token not allowed in a PDF string",
+            "Class article Warning: Unused global option(s):",
+            "Overfull \\hbox (1.2pt too wide) in paragraph at lines 3--4",
+            "Underfull \\vbox (badness 10000) has occurred while \\output is active",
+        ] {
+            assert!(is_engine_warning(line), "{}", line);
+        }
+        for line in [
+            "(/usr/share/texmf/tex/latex/warning/warning.sty)",
+            "Warning: this is the user text",
+            "Output written on main.pdf (1 page).",
+            "LaTeX Warning",
+        ] {
+            assert!(!is_engine_warning(line), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use super::{
+    compile_strategy::{run_compile_strategy, CompileStrategy},
+    download_strategy::DownloadStrategy,
+    in_place_strategy::InPlaceStrategy,
+    nfs_copy_strategy::NfsCopyStrategy,
+};
+use crate::{
+    common::metrics::app_metrics::observe_compile_result,
+    model::project::{compile_app_params::CompileAppParams, compile_settings::CompileMode},
+};
+use log::info;
+use rust_wheel::texhub::proj::compile_result::CompileResult;
+
+pub fn strategy_for_mode(mode: CompileMode) -> Box<dyn CompileStrategy> {
+    match mode {
+        CompileMode::Pipeline => Box::new(DownloadStrategy),
+        CompileMode::Nfs => Box::new(NfsCopyStrategy),
+        CompileMode::InPlace => Box::new(InPlaceStrategy),
+    }
+}
+
+/**
+ * compile the project in the mode chosen by the user `COMPILE_MODE` config
+ */
+pub async fn compile_texhub_project(params: &CompileAppParams) -> Option<CompileResult> {
+    info!(
+        "compile project, qid: {}, mode: {:?}, engine: {}",
+        params.qid,
+        params.settings.mode,
+        params.settings.engine_name()
+    );
+    let strategy = strategy_for_mode(params.settings.mode);
+    let result = run_compile_strategy(strategy.as_ref(), params).await;
+    observe_compile_result(&result, params.settings.engine_name());
+    return result;
+}
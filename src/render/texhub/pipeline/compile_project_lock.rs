@@ -0,0 +1,73 @@
+use super::pipeline_render_works::compile_deadline;
+use crate::{
+    common::cache::redis_lock::try_redis_lock,
+    model::project::compile_app_params::CompileAppParams,
+    service::compile_cancel_service::CompileCancelSignal,
+};
+use log::{error, info};
+use std::{future::Future, time::Duration};
+use tokio::time::sleep;
+
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+/// the redis errors tolerated before the job failed, the compile never runs without the lock
+const LOCK_ERROR_MAX_RETRIES: u32 = 3;
+/// the download and upload time beyond the compile deadline
+const LOCK_TTL_MARGIN: Duration = Duration::from_secs(300);
+
+pub fn compile_project_lock_key(project_id: &str) -> String {
+    return format!("texhub:compile:lock:{}", project_id);
+}
+
+pub enum ProjectLockError {
+    /// the cancel reason, the job was cancelled or superseded while waiting
+    Cancelled(String),
+    /// the lock could not be acquired because of the redis error
+    Unavailable(String),
+}
+
+/**
+ * run the compile while holding the project lock, the jobs of one project run strictly in order
+ * the waiters poll the lock without a queue, the next owner was not the oldest waiter,
+ * the waiting older jobs were superseded by the newer one so only the latest job compiles
+ */
+pub async fn with_project_lock<T, Fut: Future<Output = T>, F: FnOnce() -> Fut>(
+    params: &CompileAppParams,
+    cancel: &mut CompileCancelSignal,
+    f: F,
+) -> Result<T, ProjectLockError> {
+    let resource = compile_project_lock_key(&params.project_id);
+    let ttl = (compile_deadline() + LOCK_TTL_MARGIN).as_millis() as usize;
+    let mut waiting = false;
+    let mut errors: u32 = 0;
+    loop {
+        match try_redis_lock(&resource, ttl).await {
+            Ok(Some(_guard)) => {
+                // released when the guard was dropped, even the compile future was dropped
+                return Ok(f().await);
+            }
+            Ok(None) => {
+                if !waiting {
+                    info!(
+                        "waiting for the project compile lock, project_id: {}, qid: {}",
+                        params.project_id, params.qid
+                    );
+                    waiting = true;
+                }
+            }
+            Err(e) => {
+                errors += 1;
+                error!(
+                    "acquire project compile lock failed: {}, project_id: {}, attempt: {}",
+                    e, params.project_id, errors
+                );
+                if errors >= LOCK_ERROR_MAX_RETRIES {
+                    return Err(ProjectLockError::Unavailable(e));
+                }
+            }
+        }
+        if let Some(reason) = cancel.cancelled().await {
+            return Err(ProjectLockError::Cancelled(reason));
+        }
+        sleep(LOCK_RETRY_INTERVAL).await;
+    }
+}
@@ -0,0 +1,233 @@
+use super::{
+    compile_job::{fail_unstarted_job, CompileJob},
+    compile_log_sink::CompileLogSink,
+    compile_project_lock::{with_project_lock, ProjectLockError},
+    pipeline_render_works::{compiled_pdf_path, render_previews, run_xelatex_and_log},
+};
+use crate::{
+    common::{
+        logging::job_log_context::log_stage_finished, metrics::app_metrics::observe_compile_stage,
+        settings::app_settings::app_settings,
+    },
+    model::project::{compile_app_params::CompileAppParams, compile_job_state::CompileJobState},
+    service::compile_cancel_service::CompileCancelSignal,
+    util::fs_util::tex_filename_from_path,
+};
+use async_trait::async_trait;
+use log::{error, info, warn};
+use rust_wheel::{
+    common::util::rd_file_util::join_paths,
+    texhub::{proj::compile_result::CompileResult, project::get_proj_path},
+};
+use std::{path::Path, time::Instant};
+use tokio::fs;
+
+/**
+ * one way to compile the project: acquire the source, build it and publish the artifacts
+ * the build with the log sink was shared, so the timeout, cancel and diagnostics apply to every strategy
+ */
+#[async_trait]
+pub trait CompileStrategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// the dir the engine runs in
+    fn compile_dir(&self, params: &CompileAppParams) -> String;
+
+    /// the tex file passed to the engine, relative to the compile dir
+    fn tex_file(&self, params: &CompileAppParams) -> String {
+        return tex_filename_from_path(&params.file_path);
+    }
+
+    /// bring the project source into the compile dir
+    async fn acquire_source(
+        &self,
+        params: &CompileAppParams,
+        compile_dir: &str,
+    ) -> Result<(), String>;
+
+    /// publish the compiled pdf and the page previews
+    async fn publish(
+        &self,
+        params: &CompileAppParams,
+        pdf_path: &str,
+        previews: &[String],
+    ) -> Result<(), String>;
+
+    /// the isolated job runs in its own dir without waiting for the project lock
+    fn isolated(&self, params: &CompileAppParams) -> bool {
+        return params.isolated;
+    }
+
+    /// render the page previews beside the pdf
+    fn render_previews(&self) -> bool {
+        return true;
+    }
+
+    /// the readers tailing the log file on the shared dir wait for the legacy end marker
+    fn legacy_end_marker(&self) -> bool {
+        return false;
+    }
+
+    /// the published pdf was read from the compile dir, the dir could not be removed after the job
+    fn keeps_output(&self) -> bool {
+        return false;
+    }
+}
+
+/**
+ * the dir holding the project dir `{work_dir}/{project_id}` under the compile base dir
+ * the isolated job has its own dir
+ */
+pub fn job_work_dir(params: &CompileAppParams) -> String {
+    let texhub_output_dir = app_settings().cv.texhub_proj_compile_base_dir.clone();
+    let time_split_output_proj_base = get_proj_path(&texhub_output_dir, params.proj_created_time);
+    if params.isolated {
+        return join_paths(&[
+            time_split_output_proj_base,
+            "jobs".to_owned(),
+            params.qid.to_string(),
+        ]);
+    }
+    return time_split_output_proj_base;
+}
+
+/**
+ * the jobs of one project run in order under the project lock, unless the job was isolated
+ */
+pub async fn run_compile_strategy(
+    strategy: &dyn CompileStrategy,
+    params: &CompileAppParams,
+) -> Option<CompileResult> {
+    if strategy.isolated(params) {
+        // the parallel build of another version, did not share the project dir
+        let result = run_compile(strategy, params).await;
+        if strategy.keeps_output() {
+            return result;
+        }
+        let work_dir = job_work_dir(params);
+        if let Err(e) = fs::remove_dir_all(&work_dir).await {
+            warn!(
+                "remove isolated compile dir failed: {}, dir: {}",
+                e, work_dir
+            );
+        }
+        return result;
+    }
+    let mut cancel = CompileCancelSignal::new(&params.project_id, params.qid);
+    match with_project_lock(params, &mut cancel, || run_compile(strategy, params)).await {
+        Ok(result) => result,
+        Err(ProjectLockError::Cancelled(reason)) => {
+            // coalesced into the newer job while waiting, did not touch the project dir
+            info!("compile job {} while waiting, qid: {}", reason, params.qid);
+            let mut job = CompileJob::new(params, CompileLogSink::open(params, None));
+            job.cancel_requested().await;
+            Some(job.state().compile_result())
+        }
+        Err(ProjectLockError::Unavailable(e)) => {
+            // another worker may be compiling the project, never share the project dir
+            let message = format!("Acquire the project compile lock failed: {}", e);
+            Some(fail_unstarted_job(params, &message).await)
+        }
+    }
+}
+
+async fn run_compile(
+    strategy: &dyn CompileStrategy,
+    params: &CompileAppParams,
+) -> Option<CompileResult> {
+    let started = Instant::now();
+    let result = run_compile_steps(strategy, params).await;
+    observe_compile_stage("total", started);
+    log_stage_finished("total", started);
+    return result;
+}
+
+async fn run_compile_steps(
+    strategy: &dyn CompileStrategy,
+    params: &CompileAppParams,
+) -> Option<CompileResult> {
+    // compute compile and log paths
+    let compile_dir = strategy.compile_dir(params);
+    let log_file_path = format!("{}/{}", compile_dir, params.log_file_name);
+    let tex_file = strategy.tex_file(params);
+    let engine_log_name = format!(
+        "{}.log",
+        Path::new(&tex_file)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+    );
+    // the engine writes the full log itself when the names are the same
+    let sink_log_path = if params.log_file_name == engine_log_name {
+        None
+    } else {
+        Some(log_file_path.as_str())
+    };
+
+    // ensure compile dir
+    if let Err(e) = fs::create_dir_all(&compile_dir).await {
+        error!("ensure compile dir failed: {}, dir: {}", e, compile_dir);
+        let mut job = CompileJob::new(params, CompileLogSink::open(params, None));
+        job.transition(CompileJobState::FailedWithErrors).await;
+        return Some(job.state().compile_result());
+    }
+    let mut sink = CompileLogSink::open(params, sink_log_path);
+    sink.set_legacy_end_marker(strategy.legacy_end_marker());
+    let mut job = CompileJob::new(params, sink);
+    if job.cancel_requested().await {
+        return Some(job.state().compile_result());
+    }
+    info!(
+        "compile project with the {} strategy, qid: {}, compile_dir: {}",
+        strategy.name(),
+        params.qid,
+        compile_dir
+    );
+
+    job.transition(CompileJobState::Downloading).await;
+    if let Err(e) = strategy.acquire_source(params, &compile_dir).await {
+        error!("acquire project source failed: {}", e);
+        job.sink().line(&format!("Prepare project failed: {}", e));
+        job.transition(CompileJobState::FailedWithErrors).await;
+        return Some(job.state().compile_result());
+    }
+    if job.cancel_requested().await {
+        return Some(job.state().compile_result());
+    }
+    let warnings = match run_xelatex_and_log(&tex_file, &compile_dir, &mut job, params).await {
+        Ok(w) => w,
+        Err(e) => {
+            error!("compile step failed: {}", e);
+            return Some(job.state().compile_result());
+        }
+    };
+
+    job.transition(CompileJobState::PostProcessing).await;
+    let pdf_path = compiled_pdf_path(params, &compile_dir);
+    if !Path::new(&pdf_path).exists() {
+        warn!("Compiled PDF not found at: {}", pdf_path);
+        job.sink()
+            .line(&format!("Compiled PDF not found: {}", pdf_path));
+        job.transition(CompileJobState::FailedWithErrors).await;
+        return Some(job.state().compile_result());
+    }
+    let previews = if strategy.render_previews() {
+        render_previews(params, &pdf_path).await
+    } else {
+        Vec::new()
+    };
+
+    job.transition(CompileJobState::Uploading).await;
+    if let Err(e) = strategy.publish(params, &pdf_path, &previews).await {
+        error!("publish compiled pdf failed: {}, params: {:?}", e, params);
+        job.sink().line(&format!("Publish PDF failed: {}", e));
+        job.transition(CompileJobState::FailedWithErrors).await;
+        return Some(job.state().compile_result());
+    }
+    if warnings > 0 {
+        job.transition(CompileJobState::SucceededWithWarnings).await;
+    } else {
+        job.transition(CompileJobState::Succeeded).await;
+    }
+    return Some(job.state().compile_result());
+}
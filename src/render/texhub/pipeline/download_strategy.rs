@@ -0,0 +1,58 @@
+use super::{
+    compile_strategy::{job_work_dir, CompileStrategy},
+    pipeline_render_works::{
+        do_upload_previews_to_texhub, download_and_unzip, upload_file_to_texhub,
+    },
+};
+use crate::{
+    common::{
+        logging::job_log_context::log_stage_finished, metrics::app_metrics::observe_compile_stage,
+    },
+    model::project::compile_app_params::CompileAppParams,
+};
+use async_trait::async_trait;
+use log::info;
+use rust_wheel::common::util::rd_file_util::join_paths;
+use std::time::Instant;
+
+/**
+ * download the project zip from texhub, compile it and upload the pdf back
+ */
+pub struct DownloadStrategy;
+
+#[async_trait]
+impl CompileStrategy for DownloadStrategy {
+    fn name(&self) -> &'static str {
+        return "download";
+    }
+
+    fn compile_dir(&self, params: &CompileAppParams) -> String {
+        return join_paths(&[job_work_dir(params), params.project_id.clone()]);
+    }
+
+    async fn acquire_source(
+        &self,
+        params: &CompileAppParams,
+        compile_dir: &str,
+    ) -> Result<(), String> {
+        return download_and_unzip(params, compile_dir, &job_work_dir(params)).await;
+    }
+
+    async fn publish(
+        &self,
+        params: &CompileAppParams,
+        pdf_path: &str,
+        previews: &[String],
+    ) -> Result<(), String> {
+        info!("Uploading compiled PDF from path: {}", pdf_path);
+        // one upload observation per job, the pdf and the previews together
+        let upload_started = Instant::now();
+        let uploaded = upload_file_to_texhub(pdf_path, params, "application/pdf").await;
+        if uploaded.is_ok() {
+            do_upload_previews_to_texhub(params, previews).await;
+        }
+        observe_compile_stage("upload", upload_started);
+        log_stage_finished("upload", upload_started);
+        return uploaded;
+    }
+}
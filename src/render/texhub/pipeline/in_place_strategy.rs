@@ -0,0 +1,64 @@
+use super::compile_strategy::CompileStrategy;
+use crate::common::settings::app_settings::app_settings;
+use crate::model::project::compile_app_params::CompileAppParams;
+use async_trait::async_trait;
+use rust_wheel::{common::util::rd_file_util::join_paths, texhub::project::get_proj_path};
+use std::path::Path;
+
+/**
+ * compile in the shared nfs project dir, the outputs stay beside the source
+ */
+pub struct InPlaceStrategy;
+
+#[async_trait]
+impl CompileStrategy for InPlaceStrategy {
+    fn name(&self) -> &'static str {
+        return "in_place";
+    }
+
+    fn compile_dir(&self, params: &CompileAppParams) -> String {
+        let base_texhub_dir = app_settings().cv.texhub_proj_base_dir.clone();
+        let proj_comp_dir = get_proj_path(&base_texhub_dir, params.proj_created_time);
+        return join_paths(&[proj_comp_dir, params.project_id.clone()]);
+    }
+
+    /// the file path was relative to the project dir
+    fn tex_file(&self, params: &CompileAppParams) -> String {
+        return params.file_path.clone();
+    }
+
+    async fn acquire_source(
+        &self,
+        params: &CompileAppParams,
+        compile_dir: &str,
+    ) -> Result<(), String> {
+        let tex_path = Path::new(compile_dir).join(&params.file_path);
+        if !tex_path.exists() {
+            return Err(format!("tex file not found: {}", tex_path.display()));
+        }
+        return Ok(());
+    }
+
+    async fn publish(
+        &self,
+        _params: &CompileAppParams,
+        _pdf_path: &str,
+        _previews: &[String],
+    ) -> Result<(), String> {
+        return Ok(());
+    }
+
+    /// the shared project dir could not be isolated
+    fn isolated(&self, _params: &CompileAppParams) -> bool {
+        return false;
+    }
+
+    /// do not put the images into the user's project
+    fn render_previews(&self) -> bool {
+        return false;
+    }
+
+    fn legacy_end_marker(&self) -> bool {
+        return true;
+    }
+}
@@ -1,2 +1,9 @@
-pub mod pipeline_nfs_render_works;
-pub mod pipeline_render_works;
\ No newline at end of file
+pub mod pipeline_render_works;
+pub mod compile_log_sink;
+pub mod compile_job;
+pub mod compile_project_lock;
+pub mod compile_mode_router;
+pub mod compile_strategy;
+pub mod download_strategy;
+pub mod nfs_copy_strategy;
+pub mod in_place_strategy;
\ No newline at end of file
@@ -0,0 +1,66 @@
+use super::compile_strategy::{job_work_dir, CompileStrategy};
+use crate::common::settings::app_settings::app_settings;
+use crate::{model::project::compile_app_params::CompileAppParams, util::fs_util::copy_dir_all};
+use async_trait::async_trait;
+use rust_wheel::{common::util::rd_file_util::join_paths, texhub::project::get_proj_path};
+use std::path::Path;
+use tokio::task;
+
+/**
+ * copy the project from the shared nfs dir into the compile dir
+ * the pdf and the previews stay in the compile dir, texhub read them from the shared storage
+ */
+pub struct NfsCopyStrategy;
+
+#[async_trait]
+impl CompileStrategy for NfsCopyStrategy {
+    fn name(&self) -> &'static str {
+        return "nfs_copy";
+    }
+
+    fn compile_dir(&self, params: &CompileAppParams) -> String {
+        return join_paths(&[job_work_dir(params), params.project_id.clone()]);
+    }
+
+    async fn acquire_source(
+        &self,
+        params: &CompileAppParams,
+        compile_dir: &str,
+    ) -> Result<(), String> {
+        let proj_src_base_dir = app_settings().cv.texhub_proj_base_dir.clone();
+        let proj_time_split_dir = get_proj_path(&proj_src_base_dir, params.proj_created_time);
+        let proj_src_dir = join_paths(&[proj_time_split_dir, params.project_id.clone()]);
+        if !Path::new(&proj_src_dir).exists() {
+            return Err(format!("source project dir not found: {}", proj_src_dir));
+        }
+        // the recursive copy over nfs was slow, keep it off the runtime threads
+        let (src, dst) = (proj_src_dir.clone(), compile_dir.to_owned());
+        let copied = task::spawn_blocking(move || copy_dir_all(Path::new(&src), Path::new(&dst)))
+            .await
+            .map_err(|e| format!("copy project task failed: {}", e))?;
+        copied.map_err(|e| {
+            format!(
+                "failed to copy project to compile dir: {}, src: {}, dst: {}",
+                e, proj_src_dir, compile_dir
+            )
+        })
+    }
+
+    async fn publish(
+        &self,
+        _params: &CompileAppParams,
+        _pdf_path: &str,
+        _previews: &[String],
+    ) -> Result<(), String> {
+        return Ok(());
+    }
+
+    fn legacy_end_marker(&self) -> bool {
+        return true;
+    }
+
+    /// texhub read the pdf and the previews from the compile dir
+    fn keeps_output(&self) -> bool {
+        return true;
+    }
+}
@@ -1,101 +1,49 @@
-use crate::controller::tex::tex_controller::update_queue_compile_result_sync;
-use crate::rest::client::cv_client::http_client_sync;
+use super::compile_job::CompileJob;
+use super::compile_log_sink::{run_engine_streaming, CompileLogSink};
+use crate::common::logging::job_log_context::log_stage_finished;
+use crate::common::settings::app_settings::app_settings;
+use crate::common::metrics::app_metrics::observe_compile_stage;
+use crate::model::project::compile_job_state::CompileJobState;
+use crate::render::preview::page_preview::{render_pdf_previews, PreviewOptions};
+use crate::service::compile_log_service::compile_log_stream_key;
 use crate::{
-    model::project::compile_app_params::CompileAppParams, rest::client::cv_client::http_client,
+    model::project::compile_app_params::CompileAppParams,
+    rest::client::texhub_client::texhub_client,
 };
 use log::{error, info, warn};
-use notify::RecursiveMode;
-use notify::{Event, Watcher};
-use redis::{self, Connection};
-use rust_wheel::{
-    common::util::rd_file_util::join_paths,
-    config::app::app_conf_reader::get_app_config,
-    texhub::{proj::compile_result::CompileResult, project::get_proj_path},
-};
-use serde_json::json;
-use std::io::{Read, Seek, SeekFrom, Write};
-use std::sync::mpsc;
+use redis::{self, aio::ConnectionLike};
 use std::{
-    env,
-    fs::{self, File, OpenOptions},
-    io::Error,
+    fs::{self, File},
     path::Path,
-    process::Command,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant},
 };
-use tokio::task;
+use tokio::{fs as async_fs, process::Command, task};
+use tracing::{info_span, Instrument};
 use zip::read::ZipArchive;
 
-// Recursively copy a directory's contents from `src` to `dst`.
-#[allow(dead_code)]
-fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
-    if !dst.exists() {
-        fs::create_dir_all(dst)?;
-    }
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let ty = entry.file_type()?;
-        let dest_path = dst.join(entry.file_name());
-        if ty.is_dir() {
-            copy_dir_all(&entry.path(), &dest_path)?;
-        } else if ty.is_file() {
-            fs::copy(&entry.path(), &dest_path)?;
-        }
-        // ignore symlinks and other types
-    }
-    Ok(())
-}
-
-#[allow(dead_code)]
-fn tex_filename_from_path(path: &str) -> String {
-    Path::new(path)
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or(path)
-        .to_string()
-}
-
-#[allow(dead_code)]
-fn run_xelatex_in_dir(tex_file: &str, dir: &str) -> Result<std::process::Output, std::io::Error> {
-    Command::new("xelatex")
-        .arg(tex_file)
-        .current_dir(dir)
-        .output()
-}
+/// the engine was rerun at most this many passes to resolve the cross references
+const MAX_COMPILE_PASSES: u32 = 3;
 
 /**
  * Step 1: Download tex project source code zip package from texhub server.
  * Downloads from URL: /inner-tex/project/download/{project_id}
  * Returns path to the downloaded zip file.
  */
-async fn download_tex_project_zip(project_id: &str, temp_dir: &str) -> Result<String, String> {
-    let texhub_api_url = get_app_config("cv.texhub_api_url");
-    let url = format!("{}/inner-tex/project/download", texhub_api_url);
-    let zip_path = format!("{}/{}.zip", temp_dir, project_id);
-
-    let body = json!({"project_id": project_id, "version": "latest"});
-
-    match http_client().put(&url).json(&body).send().await {
-        Ok(resp) => {
-            if !resp.status().is_success() {
-                return Err(format!(
-                    "Download failed with status: {}, url: {}",
-                    resp.status(),
-                    url
-                ));
-            }
-            match resp.bytes().await {
-                Ok(bytes) => match fs::write(&zip_path, bytes) {
-                    Ok(_) => {
-                        info!("Downloaded tex project zip to: {}", zip_path);
-                        Ok(zip_path)
-                    }
-                    Err(e) => Err(format!("Failed to write zip file: {}", e)),
-                },
-                Err(e) => Err(format!("Failed to read response body: {}", e)),
-            }
+async fn download_tex_project_zip(
+    params: &CompileAppParams,
+    temp_dir: &str,
+) -> Result<String, String> {
+    let zip_path = format!("{}/{}.zip", temp_dir, params.project_id);
+    let bytes = texhub_client()
+        .download_project_zip(params.user_id, &params.project_id)
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?;
+    match async_fs::write(&zip_path, bytes).await {
+        Ok(_) => {
+            info!("Downloaded tex project zip to: {}", zip_path);
+            Ok(zip_path)
         }
-        Err(e) => Err(format!("HTTP request failed: {}", e)),
+        Err(e) => Err(format!("Failed to write zip file: {}", e)),
     }
 }
 
@@ -264,105 +212,120 @@ fn unzip_project(zip_path: &str, extract_dir: &str) -> Result<(), String> {
 }
 
 /**
- * Step 3 (enhanced): Run xelatex and capture stdout/stderr to a log file.
+ * Step 3 (enhanced): Run xelatex and stream stdout/stderr to the log file and redis stream.
+ * The engine was rerun when the cross references changed, all passes share one deadline.
+ * Returns the warning count of the last pass, the job was moved to the terminal state on error.
  */
-async fn run_xelatex_and_log(
+pub async fn run_xelatex_and_log(
     tex_file: &str,
     compile_dir: &str,
-    log_file_path: &str,
+    job: &mut CompileJob<'_>,
     params: &CompileAppParams,
-) -> Result<(), String> {
-    info!(
-        "Starting xelatex compilation: tex_file={}, compile_dir={}, log_file={}",
-        tex_file, compile_dir, log_file_path
-    );
-
-    let cmd = Command::new("xelatex")
-        .arg("-interaction=nonstopmode")
-        .arg("-synctex=1")
-        .arg(tex_file)
-        .current_dir(compile_dir)
-        .output();
-
-    if let Err(e) = cmd {
-        error!(
-            "Failed to start xelatex process: tex_file={}, compile_dir={}, error={}, params: {:?}",
-            tex_file, compile_dir, e, params
-        );
-        return Err(format!("Failed to start xelatex process: {}", e));
-    }
-
-    let output = cmd.unwrap();
-    let status = output.status;
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let exit_code = status
-        .code()
-        .map(|c| c.to_string())
-        .unwrap_or_else(|| "unknown (terminated by signal)".to_string());
-
-    if status.success() {
+) -> Result<usize, String> {
+    let deadline = job_deadline(params);
+    let started = Instant::now();
+    let mut pass: u32 = 1;
+    loop {
         info!(
-            "xelatex compilation succeeded: tex_file={}, compile_dir={}, exit_code={}",
-            tex_file, compile_dir, exit_code
+            "Starting {} compilation: tex_file={}, compile_dir={}, pass={}, draft={}",
+            params.settings.engine_name(),
+            tex_file,
+            compile_dir,
+            pass,
+            params.settings.draft
         );
-        if !stdout.is_empty() {
-            let stdout_len = stdout.len();
-            let preview = if stdout_len > 500 {
-                &stdout[stdout_len.saturating_sub(500)..]
-            } else {
-                &stdout
-            };
-        }
-        update_queue_compile_result_sync(params.clone(), Some(CompileResult::Success));
-        do_upload_pdf_to_texhub(params, compile_dir);
-        let _ = open_write_end_marker(log_file_path, params);
-        Ok(())
-    } else {
-        // Compilation failed - output detailed error information
-        error!(
-            "xelatex compilation failed: tex_file={}, compile_dir={}, exit_code={}",
-            tex_file, compile_dir, exit_code
+        let pass_span = info_span!(
+            "compile.engine",
+            project_id = %params.project_id,
+            qid = params.qid,
+            pass = pass,
+            engine = params.settings.engine_name()
         );
-        error!(
-            "Compilation parameters: project_id={}, file_path={}, log_file={}",
-            params.project_id, params.file_path, log_file_path
-        );
-
-        // Log stderr content (usually contains error messages)
-        if !stderr.is_empty() {
-            error!(
-                "xelatex stderr (full output, {} bytes):\n{}",
-                stderr.len(),
-                stderr
+        job.transition(CompileJobState::Compiling { pass: pass })
+            .await;
+        let mut cmd = Command::new(params.settings.engine_name());
+        cmd.arg("-interaction=nonstopmode")
+            .arg("-synctex=1")
+            .args(params.settings.engine_input_args(tex_file))
+            .current_dir(compile_dir);
+        let remaining = deadline.saturating_sub(started.elapsed());
+        let (sink, cancel) = job.engine_io();
+        let engine_started = Instant::now();
+        let outcome = run_engine_streaming(cmd, sink, cancel, remaining)
+            .instrument(pass_span)
+            .await;
+        observe_compile_stage("engine", engine_started);
+        log_stage_finished("engine", engine_started);
+        let outcome = match outcome {
+            Ok(o) => o,
+            Err(e) => {
+                error!(
+                    "Failed to start xelatex process: tex_file={}, compile_dir={}, error={}, params: {:?}",
+                    tex_file, compile_dir, e, params
+                );
+                job.transition(CompileJobState::FailedWithErrors).await;
+                return Err(format!("Failed to start xelatex process: {}", e));
+            }
+        };
+        if let Some(reason) = &outcome.cancelled {
+            info!(
+                "xelatex compilation {}: tex_file={}, pass={}",
+                reason, tex_file, pass
             );
+            job.stop_cancelled(reason).await;
+            return Err(format!("xelatex compilation {} in pass {}", reason, pass));
         }
+        let exit_status = match outcome.status {
+            Some(status) => status,
+            None => {
+                error!(
+                    "xelatex compilation timed out: tex_file={}, pass={}, params: {:?}",
+                    tex_file, pass, params
+                );
+                job.transition(CompileJobState::TimedOut).await;
+                return Err(format!("xelatex compilation timed out in pass {}", pass));
+            }
+        };
+        let exit_code = exit_status
+            .code()
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "unknown (terminated by signal)".to_string());
+        if !exit_status.success() {
+            // Compilation failed - output detailed error information
+            error!(
+                "xelatex compilation failed: tex_file={}, compile_dir={}, exit_code={}",
+                tex_file, compile_dir, exit_code
+            );
+            error!(
+                "Compilation parameters: project_id={}, file_path={}",
+                params.project_id, params.file_path
+            );
 
-        // Try to extract key error information from the output
-        let error_summary = extract_compilation_errors(&stdout, &stderr);
-        if !error_summary.is_empty() {
-            error!("Key compilation errors detected:\n{}", error_summary);
+            // Try to extract key error information from the output
+            let error_summary = extract_compilation_errors(&outcome.output_tail.join("\n"), "");
+            if !error_summary.is_empty() {
+                error!("Key compilation errors detected:\n{}", error_summary);
+            }
+            write_compilation_errors_to_log(job.sink(), &error_summary, exit_code.as_str(), params);
+            job.transition(CompileJobState::FailedWithErrors).await;
+            return Err(format!(
+                "xelatex compilation failed (exit code: {}). Check logs for details.",
+                exit_code
+            ));
         }
-
-        // Write error details to log file
-        if let Err(e) = write_compilation_errors_to_log(
-            log_file_path,
-            &stdout,
-            &stderr,
-            exit_code.as_str(),
-            params,
-        ) {
-            warn!("Failed to write compilation errors to log file: {}", e);
+        // the draft compile was a quick look, did not worth another pass
+        if outcome.rerun_requested && pass < MAX_COMPILE_PASSES && !params.settings.draft {
+            if job.cancel_requested().await {
+                return Err(format!("compile cancelled before pass {}", pass + 1));
+            }
+            pass += 1;
+            continue;
         }
-
-        let error_msg = format!(
-            "xelatex compilation failed (exit code: {}). stdout_len={}, stderr_len={}. Check logs for details.",
-            exit_code, stdout.len(), stderr.len()
+        info!(
+            "xelatex compilation succeeded: tex_file={}, compile_dir={}, passes={}, warnings={}",
+            tex_file, compile_dir, pass, outcome.warnings
         );
-
-        update_queue_compile_result_sync(params.clone(), Some(CompileResult::Failure));
-        let _ = open_write_end_marker(log_file_path, params);
-        Err(error_msg)
+        return Ok(outcome.warnings);
     }
 }
 
@@ -403,52 +366,27 @@ fn extract_compilation_errors(stdout: &str, stderr: &str) -> String {
     }
 }
 
-/// Write compilation errors to the log file
+/// Write compilation errors to the log file and redis stream
 fn write_compilation_errors_to_log(
-    log_file_path: &str,
-    stdout: &str,
-    stderr: &str,
+    sink: &mut CompileLogSink,
+    error_summary: &str,
     exit_code: &str,
     params: &CompileAppParams,
-) -> Result<(), String> {
-    let mut file = OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open(log_file_path)
-        .map_err(|e| format!("Failed to open log file: {}", e))?;
-
-    writeln!(file, "\n==== COMPILATION FAILED ====")
-        .map_err(|e| format!("Failed to write to log: {}", e))?;
-    writeln!(file, "Exit code: {}", exit_code)
-        .map_err(|e| format!("Failed to write to log: {}", e))?;
-    writeln!(file, "Project ID: {}", params.project_id)
-        .map_err(|e| format!("Failed to write to log: {}", e))?;
-    writeln!(file, "File path: {}", params.file_path)
-        .map_err(|e| format!("Failed to write to log: {}", e))?;
-
-    if !stdout.is_empty() {
-        writeln!(file, "\n--- STDOUT ---").map_err(|e| format!("Failed to write to log: {}", e))?;
-        file.write_all(stdout.as_bytes())
-            .map_err(|e| format!("Failed to write stdout to log: {}", e))?;
-    }
-
-    if !stderr.is_empty() {
-        writeln!(file, "\n--- STDERR ---").map_err(|e| format!("Failed to write to log: {}", e))?;
-        file.write_all(stderr.as_bytes())
-            .map_err(|e| format!("Failed to write stderr to log: {}", e))?;
+) {
+    sink.line("");
+    sink.line("==== COMPILATION FAILED ====");
+    sink.line(&format!("Exit code: {}", exit_code));
+    sink.line(&format!("Project ID: {}", params.project_id));
+    sink.line(&format!("File path: {}", params.file_path));
+    for line in error_summary.lines() {
+        sink.line(line);
     }
-
-    writeln!(file, "\n==== END COMPILATION ERROR ====")
-        .map_err(|e| format!("Failed to write to log: {}", e))?;
-    file.sync_all()
-        .map_err(|e| format!("Failed to sync log file: {}", e))?;
-
-    Ok(())
+    sink.line("==== END COMPILATION ERROR ====");
 }
 
-fn create_consumer_group(params: &CompileAppParams, con: &mut Connection) {
+pub async fn create_consumer_group(params: &CompileAppParams, con: &mut impl ConnectionLike) {
     // stream key namespaced by project id
-    let stream_key = format!("texhub:compile:log:{}:{}", params.project_id, params.qid);
+    let stream_key = compile_log_stream_key(&params.project_id, params.qid);
     let consumer_group = &params.project_id; // Use project_id as consumer group name
 
     // Create consumer group if it doesn't exist
@@ -460,7 +398,8 @@ fn create_consumer_group(params: &CompileAppParams, con: &mut Connection) {
         .arg(consumer_group)
         .arg("$")
         .arg("MKSTREAM")
-        .query(con);
+        .query_async(con)
+        .await;
 
     match create_group_res {
         Ok(_) => {
@@ -487,10 +426,11 @@ fn create_consumer_group(params: &CompileAppParams, con: &mut Connection) {
     }
 }
 
-pub fn del_redis_stream(params: &CompileAppParams, con: &mut Connection) {
-    let stream_key = format!("texhub:compile:log:{}:{}", params.project_id, params.qid);
+pub async fn del_redis_stream(params: &CompileAppParams, con: &mut impl ConnectionLike) {
+    let stream_key = compile_log_stream_key(&params.project_id, params.qid);
     // Clear the stream before writing new logs
-    let clear_res: redis::RedisResult<()> = redis::cmd("DEL").arg(&stream_key).query(con);
+    let clear_res: redis::RedisResult<()> =
+        redis::cmd("DEL").arg(&stream_key).query_async(con).await;
 
     if let Err(e) = clear_res {
         error!(
@@ -503,248 +443,118 @@ pub fn del_redis_stream(params: &CompileAppParams, con: &mut Connection) {
 }
 
 /**
- * Step 4: Write compile log to redis stream (optional).
- * For now, we've already written to the local log file.
- * If redis integration is needed, uncomment and implement.
- */
-fn write_log_to_redis_stream(log_content: &str, params: &CompileAppParams, con: &mut Connection) {
-    let stream_key = format!("texhub:compile:log:{}:{}", params.project_id, params.qid);
-    // Split content into lines and push each as an entry into the stream.
-    // Each entry contains fields: msg, file, project_id, proj_created_time
-    for line in log_content.lines() {
-        let res: redis::RedisResult<String> = redis::cmd("XADD")
-            .arg(&stream_key)
-            .arg("MAXLEN")
-            .arg("~")
-            .arg(5000)
-            .arg("*")
-            .arg("msg")
-            .arg(line)
-            .query(con);
-
-        if let Err(e) = res {
-            error!(
-                "Failed to XADD compile log to redis stream {}: {}. Line: {}",
-                stream_key, e, line
-            );
-        }
-    }
-}
-
-/**
- * Step 5: Upload the compiled PDF file to texhub server via HTTP.
+ * Step 5: Upload the compiled PDF file(and the page previews) to texhub server via HTTP.
  * Uses multipart form data or binary upload.
  */
-fn upload_file_to_texhub(file_path: &str, project_id: &str) -> Result<(), String> {
-    let texhub_api_url = get_app_config("cv.texhub_api_url");
-    let upload_url = format!("{}/inner-tex/project/upload-output", texhub_api_url);
-
-    let file_data = fs::read(file_path).map_err(|e| format!("Failed to read PDF file: {}", e))?;
+pub async fn upload_file_to_texhub(
+    file_path: &str,
+    params: &CompileAppParams,
+    file_content_type: &str,
+) -> Result<(), String> {
+    let project_id = params.project_id.as_str();
+    let file_data = async_fs::read(file_path)
+        .await
+        .map_err(|e| format!("Failed to read output file: {}", e))?;
 
-    // Manually build multipart/form-data body to avoid requiring reqwest multipart feature.
     let file_name = Path::new(file_path)
         .file_name()
         .map(|s| s.to_string_lossy().to_string())
         .unwrap_or_else(|| "output.pdf".to_string());
 
-    let ts = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_nanos())
-        .unwrap_or(0);
-    let boundary = format!("----rust-multipart-{}-{}", project_id, ts);
-    let mut body: Vec<u8> = Vec::new();
-
-    // project_id field
-    body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
-    body.extend_from_slice(b"Content-Disposition: form-data; name=\"project_id\"\r\n\r\n");
-    body.extend_from_slice(project_id.as_bytes());
-    body.extend_from_slice(b"\r\n");
-
-    // file field
-    body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
-    body.extend_from_slice(
-        format!(
-            "Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n",
-            file_name
+    info!("Uploading {} to texhub", file_name);
+    let upload_span = info_span!("compile.upload", project_id = project_id, file = %file_name);
+    let result = texhub_client()
+        .upload_output(
+            params.user_id,
+            project_id,
+            &file_name,
+            file_content_type,
+            &file_data,
         )
-        .as_bytes(),
-    );
-    body.extend_from_slice(b"Content-Type: application/pdf\r\n\r\n");
-    body.extend_from_slice(&file_data);
-    body.extend_from_slice(b"\r\n");
-
-    // final boundary
-    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
-
-    let content_type = format!("multipart/form-data; boundary={}", boundary);
-    info!(
-        "Uploading PDF to texhub at URL: {} (multipart manual)",
-        upload_url
-    );
-    match http_client_sync()
-        .post(&upload_url)
-        .header("Content-Type", content_type)
-        .body(body)
-        .send()
-    {
-        Ok(resp) => {
-            if resp.status().is_success() {
-                Ok(())
-            } else {
-                let status = resp.status();
-                let headers = resp.headers().clone();
-                let body_text = match resp.text() {
-                    Ok(t) => t,
-                    Err(e) => format!("<failed to read body: {}>", e),
-                };
-                error!(
-                    "PDF upload failed. url: {} status: {} headers: {:?} body: {}",
-                    upload_url, status, headers, body_text
-                );
-                Err(format!("Upload failed with status: {}", status))
-            }
-        }
-        Err(e) => {
-            error!(
-                "HTTP request to upload PDF failed: {}, url: {}",
-                e, upload_url
-            );
-            Err(format!("HTTP request failed: {}", e))
-        }
-    }
-}
-
-fn write_end_marker(file: &mut std::fs::File, params: &CompileAppParams) {
-    let wr = file.write_all("====END====\n".as_bytes());
-    if let Err(e) = wr {
-        error!("write log file failed: {}, parmas: {:?}", e, params);
-    }
-    let sync_result = file.sync_all();
-    if let Err(e) = sync_result {
-        error!("sync log file failed: {}, parmas: {:?}", e, params);
+        .instrument(upload_span)
+        .await;
+    if let Err(e) = result {
+        error!("output upload failed: {}, file: {}", e, file_name);
+        return Err(format!("Upload failed: {}", e));
     }
+    return Ok(());
 }
 
-/*
- * step 1: download the tex project source code zip package by http from texhub server
- * the url path: /inner-tex/project/download/{project_id}
- * step 2: unzip the tex project to a temp folder
- * step 3: run xelatex command to compile the tex file
- * step 4: write compile log file to redis stream
- * step 5: upload the compiled pdf file to texhub server by http
+/**
+ * the hard deadline of one compile, read from `cv.compile_timeout_secs`
  */
-pub fn render_texhub_project_pipeline(params: &CompileAppParams) -> Option<CompileResult> {
-    // compute compile and log paths
-    let texhub_output_dir = get_app_config("cv.texhub_proj_compile_base_dir");
-    let time_split_output_proj_base = get_proj_path(&texhub_output_dir, params.proj_created_time);
-    let compile_dir = join_paths(&[
-        time_split_output_proj_base.clone(),
-        params.project_id.clone(),
-    ]);
-    let log_file_path = format!("{}/{}", compile_dir, params.log_file_name);
-
-    // ensure compile dir
-    if let Err(e) = ensure_compile_dir(&compile_dir, params) {
-        error!("ensure compile dir failed: {}", e);
-        return Some(CompileResult::Failure);
-    }
-
-    // download & unzip
-    if let Err(e) = download_and_unzip(params, &compile_dir, &time_split_output_proj_base) {
-        error!("download/unzip failed: {}", e);
-        return Some(CompileResult::Failure);
-    }
-    let params_copy = params.clone();
-    let compile_dir_copy = compile_dir.clone();
-    let log_file_path_copy = log_file_path.clone();
-    // clear the log file contents before compilation
-    // check if exists, if not create
-    let _ = fs::write(&log_file_path, "");
-    let rt = tokio::runtime::Runtime::new()
-        .map_err(|e| format!("create runtime failed: {}", e))
-        .unwrap();
-    task::spawn_blocking(move || {
-        if let Err(e) = rt.block_on(compile_project(
-            &params_copy,
-            &compile_dir_copy,
-            &log_file_path_copy,
-        )) {
-            error!("compile step failed: {}", e);
-        }
-    });
-    if let Err(e) = tail_log(params, &log_file_path) {
-        error!("finalize/upload failed: {}", e);
-    }
-    Some(CompileResult::Success)
+pub fn compile_deadline() -> Duration {
+    return Duration::from_secs(app_settings().cv.compile_timeout_secs);
 }
 
-// --- Small helpers to keep pipeline readable ---
-
-fn ensure_compile_dir(compile_dir: &str, params: &CompileAppParams) -> Result<(), String> {
-    let p = Path::new(compile_dir);
-    if !p.exists() {
-        fs::create_dir_all(p).map_err(|e| format!("create compile dir failed: {}", e))?;
+/**
+ * the user could shorten the deadline with the compile settings, but never extend it
+ */
+fn job_deadline(params: &CompileAppParams) -> Duration {
+    let server_deadline = compile_deadline();
+    if params.settings.timeout_secs == 0 {
+        return server_deadline;
     }
-    Ok(())
+    return server_deadline.min(Duration::from_secs(params.settings.timeout_secs));
 }
 
-fn download_and_unzip(
+pub async fn download_and_unzip(
     params: &CompileAppParams,
     compile_dir: &str,
     unzip_dir: &str,
 ) -> Result<(), String> {
-    // temp dir for download
-    let temp_dir = format!("/tmp/texhub_downloads_{}", params.project_id);
-    fs::create_dir_all(&temp_dir).map_err(|e| format!("create temp dir failed: {}", e))?;
-
-    let rt = tokio::runtime::Runtime::new().map_err(|e| format!("create runtime failed: {}", e))?;
-    let zip_path = rt.block_on(download_tex_project_zip(&params.project_id, &temp_dir))?;
+    // temp dir for download, per job so the parallel jobs of the project did not share the zip
+    let temp_dir = format!("/tmp/texhub_downloads_{}_{}", params.project_id, params.qid);
+    async_fs::create_dir_all(&temp_dir)
+        .await
+        .map_err(|e| format!("create temp dir failed: {}", e))?;
+
+    let download_started = Instant::now();
+    let download_span = info_span!(
+        "compile.download",
+        project_id = %params.project_id,
+        qid = params.qid
+    );
+    let zip_path = download_tex_project_zip(params, &temp_dir)
+        .instrument(download_span)
+        .await?;
+    observe_compile_stage("download", download_started);
+    log_stage_finished("download", download_started);
 
     // unzip into compile_dir
     info!(
-        "About to unzip file: zip_path={}, unzip_dir={}",
-        zip_path, unzip_dir
+        "About to unzip file: zip_path={}, unzip_dir={}, compile_dir={}",
+        zip_path, unzip_dir, compile_dir
+    );
+    let unzip_started = Instant::now();
+    let unzip_span = info_span!(
+        "compile.unzip",
+        project_id = %params.project_id,
+        qid = params.qid
     );
-    match unzip_project(&zip_path, &unzip_dir) {
+    // the zip crate reads synchronously, keep it off the runtime threads
+    let (zip_src, unzip_dst) = (zip_path.clone(), unzip_dir.to_owned());
+    let unzip_result =
+        task::spawn_blocking(move || unzip_span.in_scope(|| unzip_project(&zip_src, &unzip_dst)))
+            .await
+            .unwrap_or_else(|e| Err(format!("unzip task failed: {}", e)));
+    observe_compile_stage("unzip", unzip_started);
+    log_stage_finished("unzip", unzip_started);
+    let _ = async_fs::remove_file(&zip_path).await;
+    let _ = async_fs::remove_dir_all(&temp_dir).await;
+    match unzip_result {
         Ok(_) => {
-            info!("Unzip completed successfully, cleaning up temp files");
-            let _ = fs::remove_file(&zip_path);
-            let _ = fs::remove_dir_all(&temp_dir);
+            info!("Unzip completed successfully, cleaned up temp files");
             Ok(())
         }
         Err(e) => {
-            error!("Unzip failed: {}, cleaning up temp files", e);
-            let _ = fs::remove_file(&zip_path);
-            let _ = fs::remove_dir_all(&temp_dir);
+            error!("Unzip failed: {}, cleaned up temp files", e);
             Err(format!("unzip failed: {}", e))
         }
     }
 }
 
-async fn compile_project(
-    params: &CompileAppParams,
-    compile_dir: &str,
-    log_file_path: &str,
-) -> Result<(), String> {
-    let tex_file_name = tex_filename_from_path(&params.file_path);
-    return run_xelatex_and_log(&tex_file_name, &compile_dir, log_file_path, params).await;
-}
-
-fn open_write_end_marker(log_file_path: &str, params: &CompileAppParams) -> Result<(), String> {
-    // write end marker
-    let file: Result<File, Error> = OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open(&log_file_path);
-    let mut naked_file = file.map_err(|e| format!("open log failed: {}", e))?;
-    info!("write end marker...");
-    write_end_marker(&mut naked_file, params);
-    drop(naked_file);
-    return Ok(());
-}
-
-fn do_upload_pdf_to_texhub(params: &CompileAppParams, compile_dir: &str) {
-    // upload pdf (best-effort)
+pub fn compiled_pdf_path(params: &CompileAppParams, compile_dir: &str) -> String {
     let pdf_file_name = format!(
         "{}.pdf",
         params
@@ -753,7 +563,7 @@ fn do_upload_pdf_to_texhub(params: &CompileAppParams, compile_dir: &str) {
             .next()
             .unwrap_or(&params.file_path)
     );
-    let pdf_path = format!(
+    return format!(
         "{}/{}",
         compile_dir,
         Path::new(&pdf_file_name)
@@ -761,77 +571,32 @@ fn do_upload_pdf_to_texhub(params: &CompileAppParams, compile_dir: &str) {
             .unwrap()
             .to_string_lossy()
     );
-    info!("Uploading compiled PDF from path: {}", pdf_path);
-    if Path::new(&pdf_path).exists() {
-        let _ = upload_file_to_texhub(&pdf_path, &params.project_id);
-    } else {
-        warn!("Compiled PDF not found at: {}", pdf_path);
-    }
 }
 
-fn tail_log(params: &CompileAppParams, log_file_path: &str) -> notify::Result<()> {
-    // Create Redis client and connection once, reuse for all log writes
-    let redis_url = env::var("REDIS_URL").unwrap();
-    let client = match redis::Client::open(redis_url.as_str()) {
-        Ok(c) => c,
+pub async fn render_previews(params: &CompileAppParams, pdf_path: &str) -> Vec<String> {
+    let pdf = pdf_path.to_owned();
+    let options = PreviewOptions::from_settings(&app_settings().cv);
+    // the rasterizer runs as a child process per page, keep it off the runtime threads
+    let rendered = task::spawn_blocking(move || render_pdf_previews(&pdf, &options))
+        .await
+        .unwrap_or_else(|e| Err(format!("render previews task failed: {}", e)));
+    // the page previews was best-effort, did not affect the compile result
+    match rendered {
+        Ok(previews) => previews,
         Err(e) => {
-            error!(
-                "Failed to create redis client for url {}: {}. Logging locally.",
-                redis_url, e
-            );
-            return Err(notify::Error::generic(&format!(
-                "Redis client creation failed: {}",
-                e
-            )));
+            warn!("render pdf previews failed: {}, params: {:?}", e, params);
+            Vec::new()
         }
-    };
+    }
+}
 
-    let mut con = match client.get_connection() {
-        Ok(c) => c,
-        Err(e) => {
-            error!("Failed to get redis connection: {}. Logging locally.", e);
-            return Err(notify::Error::generic(&format!(
-                "Redis connection failed: {}",
-                e
-            )));
-        }
-    };
-    del_redis_stream(&params, &mut con);
-    create_consumer_group(&params, &mut con);
-
-    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
-    // Use recommended_watcher() to automatically select the best implementation
-    // for your platform. The `EventHandler` passed to this constructor can be a
-    // closure, a `std::sync::mpsc::Sender`, a `crossbeam_channel::Sender`, or
-    // another type the trait is implemented for.
-    let mut watcher = notify::recommended_watcher(tx)?;
-    // Add a path to be watched. All files and directories at that path and
-    // below will be monitored for changes.
-    watcher.watch(Path::new(log_file_path), RecursiveMode::Recursive)?;
-    let mut contents = fs::read_to_string(&log_file_path).unwrap();
-    let mut pos = contents.len() as u64;
-    // Block forever, printing out events as they come in
-    for res in rx {
-        match res {
-            Ok(event) => {
-                let mut f = File::open(&log_file_path).unwrap();
-                f.seek(SeekFrom::Start(pos)).unwrap();
-                pos = f.metadata().unwrap().len();
-                contents.clear();
-                let read_result = f.read_to_string(&mut contents);
-                if let Err(e) = read_result {
-                    error!("read log file failed: {}", e);
-                    continue;
-                }
-                write_log_to_redis_stream(&contents, params, &mut con);
-                if contents.contains("====END====") {
-                    info!("Detected end marker in log, stopping tail.");
-                    break;
-                }
-            }
-            Err(e) => error!("watch error: {:?}", e),
-        }
+pub async fn do_upload_previews_to_texhub(params: &CompileAppParams, previews: &[String]) {
+    for preview in previews {
+        let content_type = if preview.ends_with(".webp") {
+            "image/webp"
+        } else {
+            "image/png"
+        };
+        let _ = upload_file_to_texhub(preview, params, content_type).await;
     }
-    drop(watcher);
-    Ok(())
 }
@@ -0,0 +1,141 @@
+use log::{info, warn};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/**
+ * fail fast after the consecutive failures of a dependency
+ * one probe call was let through after the open period, its result close or open the circuit again
+ */
+pub struct CircuitBreaker {
+    name: &'static str,
+    failure_threshold: u32,
+    open_period: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// the probe call in flight, another probe was allowed when it never reported
+    probe_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &'static str, failure_threshold: u32, open_period: Duration) -> Self {
+        return CircuitBreaker {
+            name: name,
+            failure_threshold: failure_threshold.max(1),
+            open_period: open_period,
+            state: Mutex::new(BreakerState::default()),
+        };
+    }
+
+    /**
+     * false when the circuit was open, the call should not be sent
+     */
+    pub fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let opened_at = match state.opened_at {
+            Some(t) => t,
+            None => return true,
+        };
+        if opened_at.elapsed() < self.open_period {
+            return false;
+        }
+        if let Some(probe_at) = state.probe_at {
+            if probe_at.elapsed() < self.open_period {
+                return false;
+            }
+        }
+        state.probe_at = Some(Instant::now());
+        return true;
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.opened_at.is_some() {
+            info!("{} circuit closed", self.name);
+        }
+        *state = BreakerState::default();
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        state.probe_at = None;
+        if state.opened_at.is_some() {
+            // the probe failed, wait another open period
+            state.opened_at = Some(Instant::now());
+            return;
+        }
+        if state.consecutive_failures >= self.failure_threshold {
+            warn!(
+                "{} circuit opened after {} consecutive failures, retry in {}s",
+                self.name,
+                state.consecutive_failures,
+                self.open_period.as_secs()
+            );
+            state.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    const OPEN_PERIOD: Duration = Duration::from_millis(50);
+
+    fn opened_breaker() -> CircuitBreaker {
+        let breaker = CircuitBreaker::new("test", 2, OPEN_PERIOD);
+        breaker.record_failure();
+        breaker.record_failure();
+        return breaker;
+    }
+
+    #[test]
+    fn circuit_opens_after_the_consecutive_failures() {
+        let breaker = CircuitBreaker::new("test", 2, OPEN_PERIOD);
+        breaker.record_failure();
+        assert!(breaker.allow());
+        breaker.record_success();
+        breaker.record_failure();
+        // the success in between reset the count
+        assert!(breaker.allow());
+        breaker.record_failure();
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn only_one_probe_was_let_through_after_the_open_period() {
+        let breaker = opened_breaker();
+        sleep(OPEN_PERIOD);
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn probe_success_closes_the_circuit() {
+        let breaker = opened_breaker();
+        sleep(OPEN_PERIOD);
+        assert!(breaker.allow());
+        breaker.record_success();
+        assert!(breaker.allow());
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn probe_failure_opens_the_circuit_again() {
+        let breaker = opened_breaker();
+        sleep(OPEN_PERIOD);
+        assert!(breaker.allow());
+        breaker.record_failure();
+        assert!(!breaker.allow());
+        sleep(OPEN_PERIOD);
+        assert!(breaker.allow());
+    }
+}
@@ -0,0 +1,209 @@
+use log::{error, warn};
+use reqwest::{header::CONTENT_TYPE, redirect::Policy, Url};
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::Path,
+    time::Duration,
+};
+use tokio::{fs, net::lookup_host};
+
+/// the photo larger than this was rejected, the cv only shows a small avatar
+const MAX_PHOTO_BYTES: usize = 5 * 1024 * 1024;
+const PHOTO_TIMEOUT: Duration = Duration::from_secs(15);
+
+/**
+ * download the cv profile photo to the compile dir
+ * return the local file path, None when the photo is absent or download failed
+ * the url was user input, only the public http(s) address was fetched
+ */
+pub async fn download_cv_photo(photo_url: &Option<String>, out_dir: &str) -> Option<String> {
+    let url = photo_url.as_deref().map(|u| u.trim()).unwrap_or_default();
+    if url.is_empty() {
+        return None;
+    }
+    match fetch_photo(url, out_dir).await {
+        Ok(photo_path) => return Some(photo_path),
+        Err(e) => {
+            error!("download cv photo failed: {}, url: {}", e, url);
+            return None;
+        }
+    }
+}
+
+async fn fetch_photo(url: &str, out_dir: &str) -> Result<String, String> {
+    let parsed = Url::parse(url).map_err(|e| format!("invalid url: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!("unsupported scheme {}", parsed.scheme()));
+    }
+    let host = parsed.host_str().ok_or("missing host")?.to_owned();
+    let addr = resolve_public_addr(&parsed).await?;
+    // pin the checked address and do not follow the redirect, it could point to the cluster
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .resolve(&host, addr)
+        .timeout(PHOTO_TIMEOUT)
+        .build()
+        .map_err(|e| format!("build client failed: {}", e))?;
+    let mut resp = client
+        .get(parsed.clone())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("status {}", resp.status()));
+    }
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    let ext = photo_ext(&content_type, parsed.path())
+        .ok_or_else(|| format!("unsupported content type {}", content_type))?;
+    if resp.content_length().unwrap_or(0) > MAX_PHOTO_BYTES as u64 {
+        return Err(format!("photo larger than {} bytes", MAX_PHOTO_BYTES));
+    }
+    // the content length could be absent or wrong, check the size while reading
+    let mut bytes: Vec<u8> = Vec::new();
+    while let Some(chunk) = resp.chunk().await.map_err(|e| e.to_string())? {
+        if bytes.len() + chunk.len() > MAX_PHOTO_BYTES {
+            return Err(format!("photo larger than {} bytes", MAX_PHOTO_BYTES));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let photo_path = format!("{}/{}.{}", out_dir, "photo", ext);
+    fs::write(Path::new(&photo_path), bytes)
+        .await
+        .map_err(|e| format!("save photo failed: {}, path: {}", e, photo_path))?;
+    return Ok(photo_path);
+}
+
+/**
+ * resolve the host and make sure every address was public
+ */
+async fn resolve_public_addr(url: &Url) -> Result<SocketAddr, String> {
+    let host = url.host_str().ok_or("missing host")?;
+    let port = url.port_or_known_default().ok_or("missing port")?;
+    // the ipv6 host was bracketed in the url
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = lookup_host((host, port))
+        .await
+        .map_err(|e| format!("resolve {} failed: {}", host, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("resolve {} failed: no address", host));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(&addr.ip())) {
+        warn!("reject the cv photo of the internal address {}", addr);
+        return Err(format!("{} resolved to the internal address", host));
+    }
+    return Ok(addrs[0]);
+}
+
+fn is_public_ipv4(ip: &Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    // 100.64.0.0/10 was the carrier grade nat range
+    let shared = octets[0] == 100 && (octets[1] & 0xc0) == 64;
+    return !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || shared
+        || octets[0] == 0);
+}
+
+fn is_public_ipv6(ip: &Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(&v4);
+    }
+    let first = ip.segments()[0];
+    // fc00::/7 the unique local, fe80::/10 the link local
+    let unique_local = (first & 0xfe00) == 0xfc00;
+    let link_local = (first & 0xffc0) == 0xfe80;
+    return !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || unique_local
+        || link_local);
+}
+
+fn is_public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_ipv4(v4),
+        IpAddr::V6(v6) => is_public_ipv6(v6),
+    }
+}
+
+/**
+ * xelatex only support the png and jpg photo, the url extension was used when
+ * the server did not tell the image type
+ */
+fn photo_ext(content_type: &str, url_path: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    match mime {
+        "image/png" => return Some("png"),
+        "image/jpeg" | "image/jpg" | "image/pjpeg" => return Some("jpg"),
+        "" | "application/octet-stream" | "binary/octet-stream" => {}
+        _ => return None,
+    }
+    let path = url_path.to_lowercase();
+    if path.ends_with(".png") {
+        return Some("png");
+    }
+    if path.ends_with(".jpg") || path.ends_with(".jpeg") {
+        return Some("jpg");
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_addresses_were_not_public() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public_ip(&ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(is_public_ip(&"93.184.216.34".parse().unwrap()));
+        assert!(is_public_ip(&"2606:4700::1111".parse().unwrap()));
+    }
+
+    #[test]
+    fn photo_ext_maps_the_image_type() {
+        assert_eq!(photo_ext("image/png", "/a"), Some("png"));
+        assert_eq!(photo_ext("image/jpeg; charset=binary", "/a"), Some("jpg"));
+        assert_eq!(photo_ext("", "/avatar.JPEG"), Some("jpg"));
+        assert_eq!(photo_ext("application/octet-stream", "/a.png"), Some("png"));
+        assert_eq!(photo_ext("image/webp", "/a.jpg"), None);
+        assert_eq!(photo_ext("image/gif", "/a.gif"), None);
+    }
+
+    #[tokio::test]
+    async fn internal_photo_urls_were_rejected() {
+        for url in [
+            "http://127.0.0.1/a.png",
+            "http://[::1]/a.png",
+            "http://169.254.169.254/latest/meta-data",
+            "file:///etc/passwd",
+            "ftp://example.com/a.png",
+        ] {
+            assert!(fetch_photo(url, "/tmp").await.is_err(), "{}", url);
+        }
+    }
+}
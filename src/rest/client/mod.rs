@@ -1,2 +1,5 @@
+pub mod circuit_breaker;
 pub mod cv_client;
-pub mod texhub_queue_client;
\ No newline at end of file
+pub mod cv_photo_client;
+pub mod texhub_client;
+pub mod texhub_error;
\ No newline at end of file
@@ -0,0 +1,587 @@
+use super::{circuit_breaker::CircuitBreaker, cv_client::http_client, texhub_error::TexhubError};
+use crate::{
+    common::{
+        auth::service_signature::{send_signed, USER_ID_HEADER},
+        settings::app_settings::{app_settings, CvSettings},
+        trace::trace_propagation::inject_trace_headers,
+    },
+    model::{
+        project::{compile_job_state::CompileJobState, tex_comp_queue::TexCompQueue},
+        request::proj::tex_proj_request::TexProjRequest,
+        user::tex_user_config::TexUserConfig,
+    },
+};
+use bytes::Bytes;
+use log::warn;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
+    RequestBuilder,
+};
+use rust_wheel::{
+    common::util::response_handler::success, model::response::api_response::ApiResponse,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+use std::{
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::sleep;
+use tracing::{info_span, Instrument};
+
+/**
+ * the texhub inner api, every call shares the signed auth headers, the retry and the circuit breaker
+ */
+pub struct TexhubClient {
+    base_url: String,
+    max_attempts: u32,
+    retry_backoff: Duration,
+    breaker: CircuitBreaker,
+}
+
+pub fn texhub_client() -> &'static TexhubClient {
+    static CLIENT: OnceLock<TexhubClient> = OnceLock::new();
+    CLIENT.get_or_init(|| TexhubClient::from_settings(&app_settings().cv))
+}
+
+/**
+ * the auth headers of the inner api acting as the user, with the trace context of the current span
+ * the user id was left out for the calls of the worker itself
+ */
+pub fn auth_headers(user_id: i64) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let token: String = app_settings().cv.x_access_token.clone();
+    match HeaderValue::from_str(&token) {
+        Ok(v) => {
+            headers.insert("x-access-token", v);
+        }
+        Err(e) => warn!("the access token was not a valid header value: {}", e),
+    }
+    if user_id > 0 {
+        headers.insert(USER_ID_HEADER, HeaderValue::from(user_id));
+    }
+    headers.insert("app-id", HeaderValue::from_static("1"));
+    headers.insert("device-id", HeaderValue::from_static("reqwest"));
+    inject_trace_headers(&mut headers);
+    return headers;
+}
+
+impl TexhubClient {
+    pub fn from_settings(cv: &CvSettings) -> Self {
+        return TexhubClient {
+            base_url: cv.texhub_api_url.clone(),
+            max_attempts: cv.texhub_retry_max_attempts.max(1),
+            retry_backoff: Duration::from_millis(cv.texhub_retry_backoff_millis),
+            breaker: CircuitBreaker::new(
+                "texhub",
+                cv.texhub_breaker_failure_threshold,
+                Duration::from_secs(cv.texhub_breaker_open_secs),
+            ),
+        };
+    }
+
+    /**
+     * update the compile queue status, return the queue record carrying the job owner
+     */
+    pub async fn update_queue_status(
+        &self,
+        user_id: i64,
+        qid: i64,
+        comp_status: i32,
+        comp_result: i32,
+    ) -> Result<TexCompQueue, TexhubError> {
+        let req = TexProjRequest {
+            comp_status: comp_status,
+            id: qid,
+            comp_result: comp_result,
+            job_state: None,
+        };
+        return self.put_queue_status(user_id, &req).await;
+    }
+
+    /**
+     * report the compile job state, the coarse status and result keep compatible
+     */
+    pub async fn update_job_state(
+        &self,
+        user_id: i64,
+        qid: i64,
+        state: &CompileJobState,
+    ) -> Result<TexCompQueue, TexhubError> {
+        let span = info_span!("texhub.update_job_state", qid = qid, state = state.name());
+        let req = TexProjRequest {
+            comp_status: state.compile_status() as i32,
+            id: qid,
+            comp_result: state.compile_result() as i32,
+            job_state: Some(state.name().to_owned()),
+        };
+        return self.put_queue_status(user_id, &req).instrument(span).await;
+    }
+
+    async fn put_queue_status(
+        &self,
+        user_id: i64,
+        req: &TexProjRequest,
+    ) -> Result<TexCompQueue, TexhubError> {
+        let url = self.url("/tex/project/compile/status");
+        // setting the same status again was harmless
+        return self
+            .call_api("update_queue_status", user_id, true, || {
+                http_client().put(&url).json(req)
+            })
+            .await;
+    }
+
+    /**
+     * let texhub mark the compile jobs running too long as expired
+     */
+    pub async fn expire_check(&self) -> Result<(), TexhubError> {
+        let url = self.url("/inner-tex/queue/expire-check");
+        let _: serde_json::Value = self
+            .call_api("expire_check", 0, false, || {
+                http_client()
+                    .post(&url)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body("{\"expire_time:\": 1}")
+            })
+            .await?;
+        return Ok(());
+    }
+
+    /**
+     * the user config of the key, None when the user did not set it
+     */
+    pub async fn get_user_config(
+        &self,
+        uid: i64,
+        key: &str,
+    ) -> Result<Option<TexUserConfig>, TexhubError> {
+        let url = self.url("/inner-tex/appconf/user-one-config");
+        let uid_param = uid.to_string();
+        return self
+            .call_api("get_user_config", uid, true, || {
+                http_client()
+                    .get(&url)
+                    .query(&[("user_id", uid_param.as_str()), ("key", key)])
+            })
+            .await;
+    }
+
+    /**
+     * download the latest project source zip
+     */
+    pub async fn download_project_zip(
+        &self,
+        user_id: i64,
+        project_id: &str,
+    ) -> Result<Bytes, TexhubError> {
+        let url = self.url("/inner-tex/project/download");
+        let body = json!({"project_id": project_id, "version": "latest"});
+        return self
+            .request("download_project", user_id, true, || {
+                http_client().put(&url).json(&body)
+            })
+            .await;
+    }
+
+    /**
+     * upload the compile output(the pdf or the page preview) of the project
+     * the multipart body was built manually, the reqwest multipart feature was not enabled
+     * the upload replaces the output of the same name, so it was retried
+     */
+    pub async fn upload_output(
+        &self,
+        user_id: i64,
+        project_id: &str,
+        file_name: &str,
+        file_content_type: &str,
+        file_data: &[u8],
+    ) -> Result<(), TexhubError> {
+        let url = self.url("/inner-tex/project/upload-output");
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let boundary = format!("----rust-multipart-{}-{}", project_id, ts);
+        let mut body: Vec<u8> = Vec::new();
+
+        // project_id field
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        body.extend_from_slice(b"Content-Disposition: form-data; name=\"project_id\"\r\n\r\n");
+        body.extend_from_slice(project_id.as_bytes());
+        body.extend_from_slice(b"\r\n");
+
+        // file field
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        body.extend_from_slice(
+            format!(
+                "Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n",
+                file_name
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(format!("Content-Type: {}\r\n\r\n", file_content_type).as_bytes());
+        body.extend_from_slice(file_data);
+        body.extend_from_slice(b"\r\n");
+
+        // final boundary
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        let body = Bytes::from(body);
+        let content_type = format!("multipart/form-data; boundary={}", boundary);
+        self.request("upload_output", user_id, true, || {
+            http_client()
+                .post(&url)
+                .header(CONTENT_TYPE, content_type.as_str())
+                .body(body.clone())
+        })
+        .await?;
+        return Ok(());
+    }
+
+    fn url(&self, path: &str) -> String {
+        return format!("{}{}", self.base_url, path);
+    }
+
+    /**
+     * send the request and decode the texhub `ApiResponse`, the failure result code was an error
+     */
+    async fn call_api<T, F>(
+        &self,
+        call: &str,
+        user_id: i64,
+        idempotent: bool,
+        build: F,
+    ) -> Result<T, TexhubError>
+    where
+        T: DeserializeOwned + Serialize,
+        F: Fn() -> RequestBuilder,
+    {
+        let body = self.request(call, user_id, idempotent, build).await?;
+        let resp: ApiResponse<T> =
+            serde_json::from_slice(&body).map_err(|e| TexhubError::Decode {
+                message: e.to_string(),
+                body: String::from_utf8_lossy(&body).into_owned(),
+            })?;
+        if !success(&resp) {
+            return Err(TexhubError::Rejected(
+                serde_json::to_string(&resp).unwrap_or_default(),
+            ));
+        }
+        return Ok(resp.result);
+    }
+
+    /**
+     * send the request built by `build` acting as the user and read the 2xx body
+     * the idempotent call was retried with the exponential backoff on the transient failure
+     */
+    async fn request<F>(
+        &self,
+        call: &str,
+        user_id: i64,
+        idempotent: bool,
+        build: F,
+    ) -> Result<Bytes, TexhubError>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let max_attempts = if idempotent { self.max_attempts } else { 1 };
+        let mut backoff = self.retry_backoff;
+        let mut attempt: u32 = 1;
+        loop {
+            if !self.breaker.allow() {
+                return Err(TexhubError::CircuitOpen);
+            }
+            let result = send_once(build(), user_id).await;
+            match &result {
+                Err(e) if e.retryable() => self.breaker.record_failure(),
+                _ => self.breaker.record_success(),
+            }
+            match result {
+                Err(e) if e.retryable() && attempt < max_attempts => {
+                    warn!(
+                        "texhub {} failed: {}, attempt {}/{}, retry in {}ms",
+                        call,
+                        e,
+                        attempt,
+                        max_attempts,
+                        backoff.as_millis()
+                    );
+                    sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+async fn send_once(req: RequestBuilder, user_id: i64) -> Result<Bytes, TexhubError> {
+    let resp = send_signed(req.headers(auth_headers(user_id))).await?;
+    let status = resp.status();
+    let body = resp.bytes().await?;
+    if !status.is_success() {
+        return Err(TexhubError::Status {
+            status: status.as_u16(),
+            body: String::from_utf8_lossy(&body).into_owned(),
+        });
+    }
+    return Ok(body);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, web, App, HttpRequest, HttpResponse, HttpServer};
+    use std::{collections::VecDeque, sync::Mutex};
+
+    struct RecordedRequest {
+        method: String,
+        path: String,
+        query: String,
+        user_id: Option<String>,
+        body: Vec<u8>,
+    }
+
+    /**
+     * the texhub stub, answer the scripted statuses in order and then 200 with the body
+     */
+    struct MockTexhub {
+        statuses: Mutex<VecDeque<u16>>,
+        body: String,
+        requests: Mutex<Vec<RecordedRequest>>,
+    }
+
+    impl MockTexhub {
+        fn requests(&self) -> std::sync::MutexGuard<'_, Vec<RecordedRequest>> {
+            return self.requests.lock().unwrap();
+        }
+    }
+
+    async fn mock_handler(
+        req: HttpRequest,
+        body: web::Bytes,
+        mock: web::Data<MockTexhub>,
+    ) -> HttpResponse {
+        mock.requests().push(RecordedRequest {
+            method: req.method().to_string(),
+            path: req.path().to_owned(),
+            query: req.query_string().to_owned(),
+            user_id: req
+                .headers()
+                .get(USER_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned()),
+            body: body.to_vec(),
+        });
+        let status = mock.statuses.lock().unwrap().pop_front().unwrap_or(200);
+        let status = StatusCode::from_u16(status).unwrap();
+        if !status.is_success() {
+            return HttpResponse::build(status).body("texhub unavailable");
+        }
+        return HttpResponse::Ok().body(mock.body.clone());
+    }
+
+    async fn start_mock(statuses: &[u16], body: String) -> (String, web::Data<MockTexhub>) {
+        let mock = web::Data::new(MockTexhub {
+            statuses: Mutex::new(statuses.iter().copied().collect()),
+            body: body,
+            requests: Mutex::new(Vec::new()),
+        });
+        let data = mock.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .default_service(web::to(mock_handler))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = server.addrs()[0];
+        tokio::spawn(server.run());
+        return (format!("http://{}", addr), mock);
+    }
+
+    fn mock_client(base_url: &str, max_attempts: u32, failure_threshold: u32) -> TexhubClient {
+        return TexhubClient {
+            base_url: base_url.to_owned(),
+            max_attempts: max_attempts,
+            retry_backoff: Duration::from_millis(1),
+            breaker: CircuitBreaker::new("texhub", failure_threshold, Duration::from_secs(60)),
+        };
+    }
+
+    fn api_body<T: Serialize + Default>(result: T) -> String {
+        let resp = ApiResponse {
+            result: result,
+            ..Default::default()
+        };
+        return serde_json::to_string(&resp).unwrap();
+    }
+
+    fn queue_of(user_id: i64) -> TexCompQueue {
+        return TexCompQueue {
+            id: 11,
+            user_id: user_id,
+            ..Default::default()
+        };
+    }
+
+    #[tokio::test]
+    async fn update_queue_status_puts_the_status_as_the_user() {
+        let (url, mock) = start_mock(&[], api_body(queue_of(7))).await;
+        let queue = mock_client(&url, 3, 5)
+            .update_queue_status(7, 11, 1, -1)
+            .await
+            .unwrap();
+        assert_eq!(queue.user_id, 7);
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(requests[0].path, "/tex/project/compile/status");
+        assert_eq!(requests[0].user_id.as_deref(), Some("7"));
+        let sent: TexProjRequest = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            sent,
+            TexProjRequest {
+                comp_status: 1,
+                id: 11,
+                comp_result: -1,
+                job_state: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn update_job_state_sends_the_state_name() {
+        let (url, mock) = start_mock(&[], api_body(queue_of(7))).await;
+        mock_client(&url, 3, 5)
+            .update_job_state(7, 11, &CompileJobState::Succeeded)
+            .await
+            .unwrap();
+        let requests = mock.requests();
+        let sent: TexProjRequest = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(sent.id, 11);
+        assert_eq!(sent.job_state.as_deref(), Some("succeeded"));
+    }
+
+    #[tokio::test]
+    async fn expire_check_posts_without_the_user() {
+        let (url, mock) = start_mock(&[], api_body(serde_json::Value::Null)).await;
+        mock_client(&url, 3, 5).expire_check().await.unwrap();
+        let requests = mock.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/inner-tex/queue/expire-check");
+        assert_eq!(requests[0].user_id, None);
+    }
+
+    #[tokio::test]
+    async fn get_user_config_queries_the_key() {
+        let config = TexUserConfig {
+            config_key: "compile".to_owned(),
+            config_value: "{}".to_owned(),
+            user_id: 7,
+            ..Default::default()
+        };
+        let (url, mock) = start_mock(&[], api_body(Some(config))).await;
+        let found = mock_client(&url, 3, 5)
+            .get_user_config(7, "compile")
+            .await
+            .unwrap();
+        assert_eq!(found.unwrap().config_key, "compile");
+        let requests = mock.requests();
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/inner-tex/appconf/user-one-config");
+        assert_eq!(requests[0].query, "user_id=7&key=compile");
+    }
+
+    #[tokio::test]
+    async fn download_project_zip_returns_the_raw_body() {
+        let (url, mock) = start_mock(&[], "PK-zip-bytes".to_owned()).await;
+        let zip = mock_client(&url, 3, 5)
+            .download_project_zip(7, "p1")
+            .await
+            .unwrap();
+        assert_eq!(zip.as_ref(), b"PK-zip-bytes");
+        let requests = mock.requests();
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(requests[0].path, "/inner-tex/project/download");
+        let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(sent, json!({"project_id": "p1", "version": "latest"}));
+    }
+
+    #[tokio::test]
+    async fn upload_output_posts_the_multipart_file() {
+        let (url, mock) = start_mock(&[], String::new()).await;
+        mock_client(&url, 3, 5)
+            .upload_output(7, "p1", "main.pdf", "application/pdf", b"%PDF-1.5")
+            .await
+            .unwrap();
+        let requests = mock.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/inner-tex/project/upload-output");
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert!(body.contains("name=\"project_id\"\r\n\r\np1\r\n"));
+        assert!(body.contains("filename=\"main.pdf\""));
+        assert!(body.contains("Content-Type: application/pdf\r\n\r\n%PDF-1.5\r\n"));
+    }
+
+    #[tokio::test]
+    async fn idempotent_call_was_retried_on_5xx() {
+        let (url, mock) = start_mock(&[503, 502], api_body(queue_of(7))).await;
+        let queue = mock_client(&url, 3, 5)
+            .update_queue_status(7, 11, 1, -1)
+            .await
+            .unwrap();
+        assert_eq!(queue.id, 11);
+        assert_eq!(mock.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn retry_stops_at_the_max_attempts() {
+        let (url, mock) = start_mock(&[503, 503, 503, 503], api_body(queue_of(7))).await;
+        let result = mock_client(&url, 3, 5)
+            .update_queue_status(7, 11, 1, -1)
+            .await;
+        assert!(matches!(
+            result,
+            Err(TexhubError::Status { status: 503, .. })
+        ));
+        assert_eq!(mock.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn non_idempotent_call_was_not_retried() {
+        let (url, mock) = start_mock(&[503], api_body(serde_json::Value::Null)).await;
+        let result = mock_client(&url, 3, 5).expire_check().await;
+        assert!(matches!(
+            result,
+            Err(TexhubError::Status { status: 503, .. })
+        ));
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn client_error_was_not_retried() {
+        let (url, mock) = start_mock(&[404], String::new()).await;
+        let result = mock_client(&url, 3, 5).download_project_zip(7, "p1").await;
+        assert!(matches!(
+            result,
+            Err(TexhubError::Status { status: 404, .. })
+        ));
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn open_circuit_fails_fast_without_sending() {
+        let (url, mock) = start_mock(&[503, 503], String::new()).await;
+        let client = mock_client(&url, 1, 2);
+        for _ in 0..2 {
+            assert!(client.download_project_zip(7, "p1").await.is_err());
+        }
+        let result = client.download_project_zip(7, "p1").await;
+        assert!(matches!(result, Err(TexhubError::CircuitOpen)));
+        assert_eq!(mock.requests().len(), 2);
+    }
+}
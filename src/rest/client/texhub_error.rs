@@ -0,0 +1,59 @@
+use std::fmt;
+
+/**
+ * the failure of one texhub api call
+ */
+#[derive(Debug)]
+pub enum TexhubError {
+    /// the circuit breaker was open, the request was not sent
+    CircuitOpen,
+    /// connect failed, timed out or the body could not be read
+    Transport(String),
+    /// texhub answered with a non-2xx status
+    Status { status: u16, body: String },
+    /// the body was not the expected json
+    Decode { message: String, body: String },
+    /// texhub answered with the failure result code
+    Rejected(String),
+}
+
+impl TexhubError {
+    /**
+     * the transient failure worth another attempt, it was counted by the circuit breaker too
+     */
+    pub fn retryable(&self) -> bool {
+        match self {
+            TexhubError::Transport(_) => true,
+            TexhubError::Status { status, .. } => *status >= 500 || *status == 429,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TexhubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TexhubError::CircuitOpen => write!(f, "texhub circuit open"),
+            TexhubError::Transport(e) => write!(f, "texhub request failed: {}", e),
+            TexhubError::Status { status, body } => {
+                write!(f, "texhub responded status {}, body: {}", status, body)
+            }
+            TexhubError::Decode { message, body } => {
+                write!(
+                    f,
+                    "parse texhub response failed: {}, body: {}",
+                    message, body
+                )
+            }
+            TexhubError::Rejected(resp) => write!(f, "texhub rejected the request: {}", resp),
+        }
+    }
+}
+
+impl std::error::Error for TexhubError {}
+
+impl From<reqwest::Error> for TexhubError {
+    fn from(e: reqwest::Error) -> Self {
+        return TexhubError::Transport(e.to_string());
+    }
+}
@@ -1,40 +1,51 @@
-use crate::{model::user::tex_user_config::TexUserConfig, rest::client::cv_client::http_client};
+use crate::common::settings::app_settings::app_settings;
+use crate::{
+    model::user::tex_user_config::TexUserConfig,
+    rest::client::{texhub_client::texhub_client, texhub_error::TexhubError},
+};
 use log::error;
-use rust_wheel::{
-    config::app::app_conf_reader::get_app_config, model::response::api_response::ApiResponse,
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
 };
 
-pub async fn get_one_user_config(uid: i64, key: &str) -> Option<TexUserConfig> {
-    let url_path = format!(
-        "{}{}{}{}",
-        "/inner-tex/appconf/user-one-config?user_id=", uid, "&key=", key
-    );
-    let url = format!("{}{}", get_app_config("cv.texhub_api_url"), url_path);
-    // Send request and bail out early on error to keep nesting shallow.
-    let resp = match http_client().get(url.clone()).send().await {
-        Ok(r) => r,
-        Err(e) => {
-            error!("get user conf error: {}, url: {}", e, url);
-            return None;
-        }
-    };
+type UserConfigCache = HashMap<(i64, String), (Instant, Option<TexUserConfig>)>;
+
+fn user_config_cache() -> &'static Mutex<UserConfigCache> {
+    static CACHE: OnceLock<Mutex<UserConfigCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
 
-    // Read body as text so we can log raw response for debugging, then parse JSON.
-    let body_text = match resp.text().await {
-        Ok(t) => t,
+/**
+ * the user config with a short-lived cache, the missing config was cached too
+ * the cache ttl read from `cv.user_config_cache_secs`
+ */
+pub async fn get_one_user_config_cached(uid: i64, key: &str) -> Option<TexUserConfig> {
+    let ttl = Duration::from_secs(app_settings().cv.user_config_cache_secs);
+    let cache_key = (uid, key.to_owned());
+    if let Some((fetched, conf)) = user_config_cache().lock().unwrap().get(&cache_key) {
+        if fetched.elapsed() < ttl {
+            return conf.clone();
+        }
+    }
+    // the failed fetch was not cached, the next compile asks texhub again
+    let conf = match get_one_user_config(uid, key).await {
+        Ok(c) => c,
         Err(e) => {
-            error!("read response body failed: {}, url: {}", e, url);
+            error!("get user conf error: {}, uid: {}, key: {}", e, uid, key);
             return None;
         }
     };
-    match serde_json::from_str::<ApiResponse<TexUserConfig>>(&body_text) {
-        Ok(api_resp) => Some(api_resp.result),
-        Err(e) => {
-            error!(
-                "parse user config info error: {}, url: {}, body: {}",
-                e, url, body_text
-            );
-            None
-        }
-    }
+    let mut cache = user_config_cache().lock().unwrap();
+    cache.retain(|_, (fetched, _)| fetched.elapsed() < ttl);
+    cache.insert(cache_key, (Instant::now(), conf.clone()));
+    return conf;
+}
+
+pub async fn get_one_user_config(
+    uid: i64,
+    key: &str,
+) -> Result<Option<TexUserConfig>, TexhubError> {
+    return texhub_client().get_user_config(uid, key).await;
 }
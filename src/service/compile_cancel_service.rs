@@ -0,0 +1,264 @@
+use crate::{
+    common::cache::redis_async::shared_redis,
+    task::app_shutdown::{compile_interrupted, REQUEUE_REASON},
+};
+use log::{error, info, warn};
+use redis::{AsyncCommands, RedisResult, Script};
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+/// the cancel signal and the active job key expire after the compile could not run anymore
+const SIGNAL_TTL_SECS: u64 = 3600;
+/// the running engine poll the cancel signal in this interval
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const CANCEL_KEY_PREFIX: &str = "texhub:compile:cancel:";
+
+/**
+ * the cancel request on behalf of a user
+ */
+pub enum CancelOutcome {
+    /// the cancelled qid, None when the project had no active job
+    Requested(Option<i64>),
+    /// the job was owned by another user, or the owner was unknown
+    NotOwner,
+}
+
+/**
+ * KEYS[1] the active job key, ARGV[1] the qid, ARGV[2] the cancel key prefix, ARGV[3] the ttl
+ * return the superseded qid, 0 when nothing was superseded
+ */
+const SUPERSEDE_SCRIPT: &str = r"
+local prev = tonumber(redis.call('GET', KEYS[1]) or '0')
+local qid = tonumber(ARGV[1])
+if prev == qid then
+    return 0
+end
+if prev > qid then
+    redis.call('SET', ARGV[2] .. ARGV[1], 'superseded', 'EX', ARGV[3])
+    return qid
+end
+redis.call('SET', KEYS[1], ARGV[1], 'EX', ARGV[3])
+if prev > 0 then
+    redis.call('SET', ARGV[2] .. prev, 'superseded', 'EX', ARGV[3])
+end
+return prev
+";
+
+/// KEYS[1] the active job key, ARGV[1] the qid, delete the key only when it still points to the qid
+const CLEAR_ACTIVE_SCRIPT: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+";
+
+fn supersede_script() -> &'static Script {
+    static SCRIPT: OnceLock<Script> = OnceLock::new();
+    return SCRIPT.get_or_init(|| Script::new(SUPERSEDE_SCRIPT));
+}
+
+fn clear_active_script() -> &'static Script {
+    static SCRIPT: OnceLock<Script> = OnceLock::new();
+    return SCRIPT.get_or_init(|| Script::new(CLEAR_ACTIVE_SCRIPT));
+}
+
+pub fn compile_cancel_key(qid: i64) -> String {
+    return format!("{}{}", CANCEL_KEY_PREFIX, qid);
+}
+
+/// the latest compile job qid of the project
+pub fn compile_active_key(project_id: &str) -> String {
+    return format!("texhub:compile:active:{}", project_id);
+}
+
+/// the user id of the compile job, checked before cancelling on behalf of a user
+pub fn compile_owner_key(qid: i64) -> String {
+    return format!("texhub:compile:owner:{}", qid);
+}
+
+/**
+ * record the owner of the accepted job, the user could only cancel the own jobs
+ */
+pub async fn record_job_owner(qid: i64, user_id: i64) {
+    let result: RedisResult<()> = match shared_redis().await {
+        Ok(mut con) => {
+            con.set_ex(compile_owner_key(qid), user_id, SIGNAL_TTL_SECS)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        error!("record compile job owner failed: {}, qid: {}", e, qid);
+    }
+}
+
+async fn owned_by(
+    con: &mut impl AsyncCommands,
+    qid: i64,
+    acting_user: Option<i64>,
+) -> RedisResult<bool> {
+    let user_id = match acting_user {
+        Some(u) => u,
+        None => return Ok(true),
+    };
+    let owner: Option<i64> = con.get(compile_owner_key(qid)).await?;
+    return Ok(owner == Some(user_id));
+}
+
+/**
+ * send the cancel signal to the compile job on behalf of the acting user, the job of the others was not touched
+ * the queued job was skipped and the running engine was killed when it sees the signal
+ */
+pub async fn request_cancel_job(qid: i64, acting_user: Option<i64>) -> RedisResult<CancelOutcome> {
+    let mut con = shared_redis().await?;
+    if !owned_by(&mut con, qid, acting_user).await? {
+        return Ok(CancelOutcome::NotOwner);
+    }
+    let _: () = con
+        .set_ex(compile_cancel_key(qid), "cancelled", SIGNAL_TTL_SECS)
+        .await?;
+    info!("compile cancel requested, qid: {}", qid);
+    return Ok(CancelOutcome::Requested(Some(qid)));
+}
+
+/**
+ * cancel the latest compile job of the project on behalf of the acting user
+ */
+pub async fn request_cancel_project(
+    project_id: &str,
+    acting_user: Option<i64>,
+) -> RedisResult<CancelOutcome> {
+    let mut con = shared_redis().await?;
+    let qid: Option<i64> = con.get(compile_active_key(project_id)).await?;
+    let q = match qid {
+        Some(q) => q,
+        None => return Ok(CancelOutcome::Requested(None)),
+    };
+    if !owned_by(&mut con, q, acting_user).await? {
+        return Ok(CancelOutcome::NotOwner);
+    }
+    let _: () = con
+        .set_ex(compile_cancel_key(q), "cancelled", SIGNAL_TTL_SECS)
+        .await?;
+    info!(
+        "compile cancel requested, qid: {}, project_id: {}",
+        q, project_id
+    );
+    return Ok(CancelOutcome::Requested(Some(q)));
+}
+
+/**
+ * record the job as the latest one of the project and supersede the older job
+ * the job itself was superseded when a newer job of the project already arrived
+ * the qid was the texhub queue row id allocated in the submit order, the larger one was the newer job
+ * the compare and the writes ran in one script so the workers could not supersede each other
+ */
+pub async fn supersede_older_job(project_id: &str, qid: i64) {
+    let mut con = match shared_redis().await {
+        Ok(c) => c,
+        Err(e) => {
+            error!("open redis connection for supersede failed: {}", e);
+            return;
+        }
+    };
+    let active_key = compile_active_key(project_id);
+    let superseded: RedisResult<i64> = supersede_script()
+        .key(&active_key)
+        .arg(qid)
+        .arg(CANCEL_KEY_PREFIX)
+        .arg(SIGNAL_TTL_SECS)
+        .invoke_async(&mut con)
+        .await;
+    match superseded {
+        Ok(0) => {}
+        Ok(s) => info!(
+            "compile job superseded, qid: {}, project_id: {}",
+            s, project_id
+        ),
+        Err(e) => error!(
+            "supersede compile job failed: {}, key: {}, qid: {}",
+            e, active_key, qid
+        ),
+    }
+}
+
+/**
+ * the cancel signal of one running compile job, the redis was polled at most once per interval
+ */
+#[derive(Clone)]
+pub struct CompileCancelSignal {
+    qid: i64,
+    project_id: String,
+    last_poll: Option<Instant>,
+    reason: Option<String>,
+}
+
+impl CompileCancelSignal {
+    pub fn new(project_id: &str, qid: i64) -> Self {
+        return CompileCancelSignal {
+            qid: qid,
+            project_id: project_id.to_owned(),
+            last_poll: None,
+            reason: None,
+        };
+    }
+
+    /**
+     * return the cancel reason when the job was cancelled, superseded or requeued by the shutdown
+     */
+    pub async fn cancelled(&mut self) -> Option<String> {
+        if self.reason.is_some() {
+            return self.reason.clone();
+        }
+        if compile_interrupted() {
+            self.reason = Some(REQUEUE_REASON.to_owned());
+            return self.reason.clone();
+        }
+        if let Some(last) = self.last_poll {
+            if last.elapsed() < POLL_INTERVAL {
+                return None;
+            }
+        }
+        self.last_poll = Some(Instant::now());
+        let mut con = match shared_redis().await {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("open redis connection for cancel signal failed: {}", e);
+                return None;
+            }
+        };
+        let reason: RedisResult<Option<String>> = con.get(compile_cancel_key(self.qid)).await;
+        match reason {
+            Ok(r) => self.reason = r,
+            Err(e) => warn!(
+                "poll compile cancel signal failed: {}, qid: {}",
+                e, self.qid
+            ),
+        }
+        return self.reason.clone();
+    }
+
+    /**
+     * clear the signal, the owner and the active job record when the job finished
+     */
+    pub async fn clear(&self) {
+        let mut con = match shared_redis().await {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("open redis connection for cancel signal failed: {}", e);
+                return;
+            }
+        };
+        let _: RedisResult<()> = con
+            .del(&[compile_cancel_key(self.qid), compile_owner_key(self.qid)])
+            .await;
+        // the newer job of the project may already own the key
+        let _: RedisResult<i64> = clear_active_script()
+            .key(compile_active_key(&self.project_id))
+            .arg(self.qid)
+            .invoke_async(&mut con)
+            .await;
+    }
+}
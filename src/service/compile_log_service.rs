@@ -0,0 +1,158 @@
+use crate::common::{cache::redis_async::dedicated_redis, settings::app_settings::app_settings};
+use crate::model::{
+    request::proj::compile_log_params::CompileLogParams,
+    response::tex::compile_log_event::CompileLogEvent,
+};
+use log::{error, warn};
+use redis::{
+    streams::{StreamId, StreamReadOptions, StreamReadReply},
+    AsyncCommands, RedisResult,
+};
+use std::{
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc::UnboundedSender, OwnedSemaphorePermit, Semaphore};
+
+/// the end marker written by the legacy eden mode compile log
+pub const COMPILE_LOG_END_MARKER: &str = "====END====";
+const READ_BLOCK_MILLIS: usize = 5000;
+const READ_BATCH_SIZE: usize = 100;
+/// stop the subscription when no log arrived in this duration
+const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+pub fn compile_log_stream_key(project_id: &str, qid: i64) -> String {
+    return format!("texhub:compile:log:{}:{}", project_id, qid);
+}
+
+fn subscriber_slots() -> &'static Arc<Semaphore> {
+    static SLOTS: OnceLock<Arc<Semaphore>> = OnceLock::new();
+    SLOTS.get_or_init(|| {
+        Arc::new(Semaphore::new(
+            app_settings().cv.compile_log_max_subscribers,
+        ))
+    })
+}
+
+/**
+ * take a subscriber slot, None when `cv.compile_log_max_subscribers` viewers were reading
+ * the slot was released when the subscription ended
+ */
+pub fn acquire_log_subscriber() -> Option<OwnedSemaphorePermit> {
+    return subscriber_slots().clone().try_acquire_owned().ok();
+}
+
+/**
+ * read the compile log stream with `XREAD BLOCK` and forward the typed events
+ * until the end event, the idle timeout or the receiver closed
+ */
+pub async fn subscribe_compile_log(
+    params: CompileLogParams,
+    tx: UnboundedSender<CompileLogEvent>,
+    _slot: OwnedSemaphorePermit,
+) {
+    // the XREAD BLOCK holds the connection, each subscription owns one
+    let con = dedicated_redis().await;
+    if let Err(e) = con {
+        error!("open redis connection for compile log failed: {}", e);
+        let _ = tx.send(CompileLogEvent::Error {
+            message: "log stream unavailable".to_owned(),
+        });
+        return;
+    }
+    let mut con = con.unwrap();
+    let stream_key = compile_log_stream_key(&params.project_id, params.qid);
+    let mut last_id = params.last_id.unwrap_or("0".to_owned());
+    let mut idle_since = Instant::now();
+    loop {
+        let options = StreamReadOptions::default()
+            .count(READ_BATCH_SIZE)
+            .block(READ_BLOCK_MILLIS);
+        // the viewer left while blocking, drop the read rather than waiting the block timeout
+        let result: RedisResult<StreamReadReply> = tokio::select! {
+            _ = tx.closed() => return,
+            result = con.xread_options(&[stream_key.as_str()], &[last_id.as_str()], &options) => result,
+        };
+        if let Err(e) = result {
+            error!("read compile log stream failed: {}, key: {}", e, stream_key);
+            let _ = tx.send(CompileLogEvent::Error {
+                message: "read log stream failed".to_owned(),
+            });
+            return;
+        }
+        let entries: Vec<StreamId> = result
+            .unwrap()
+            .keys
+            .into_iter()
+            .flat_map(|k| k.ids)
+            .collect();
+        if entries.is_empty() {
+            if idle_since.elapsed() > IDLE_TIMEOUT {
+                warn!("compile log idle timeout, key: {}", stream_key);
+                let _ = tx.send(CompileLogEvent::Error {
+                    message: "log stream idle timeout".to_owned(),
+                });
+                return;
+            }
+            continue;
+        }
+        idle_since = Instant::now();
+        for entry in entries {
+            last_id = entry.id.clone();
+            let event = parse_log_entry(&entry);
+            let terminal = event.is_terminal();
+            if tx.send(event).is_err() || terminal {
+                return;
+            }
+        }
+    }
+}
+
+fn parse_log_entry(entry: &StreamId) -> CompileLogEvent {
+    let id = entry.id.clone();
+    if let Some(status) = entry.get::<String>("status") {
+        let pass = entry.get::<u32>("pass");
+        return CompileLogEvent::Status { id, status, pass };
+    }
+    if let Some(result) = entry.get::<String>("end") {
+        return CompileLogEvent::End { id, result };
+    }
+    let line: String = entry.get("msg").unwrap_or_default();
+    if line.trim() == COMPILE_LOG_END_MARKER {
+        return CompileLogEvent::End {
+            id,
+            result: "unknown".to_owned(),
+        };
+    }
+    match diagnostic_level(&line) {
+        Some(level) => {
+            return CompileLogEvent::Diagnostic {
+                id,
+                level: level.to_owned(),
+                line,
+            }
+        }
+        None => return CompileLogEvent::Log { id, line },
+    }
+}
+
+/**
+ * the tex engine error start with `!` or `file:line:` in file-line-error mode
+ */
+fn diagnostic_level(line: &str) -> Option<&'static str> {
+    if line.starts_with("! ") || is_file_line_error(line) {
+        return Some("error");
+    }
+    if line.contains("Warning:") || line.starts_with("Overfull") || line.starts_with("Underfull") {
+        return Some("warning");
+    }
+    return None;
+}
+
+fn is_file_line_error(line: &str) -> bool {
+    let parts: Vec<&str> = line.splitn(3, ':').collect();
+    return parts.len() == 3
+        && parts[0].ends_with(".tex")
+        && !parts[1].is_empty()
+        && parts[1].chars().all(|c| c.is_ascii_digit());
+}
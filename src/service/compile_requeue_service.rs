@@ -0,0 +1,54 @@
+use crate::{
+    common::{cache::redis_async::shared_redis, settings::app_settings::app_settings},
+    model::project::{compile_app_params::CompileAppParams, compile_job_state::CompileJobState},
+    rest::client::texhub_client::texhub_client,
+};
+use log::{error, info};
+use redis::RedisResult;
+
+/**
+ * put the compile job back to the compile stream, another worker picks it up
+ * the fields were the same as the texhub server sends
+ */
+pub async fn requeue_compile_job(params: &CompileAppParams) -> bool {
+    let result = xadd_compile_record(params).await;
+    if let Err(e) = result {
+        error!("requeue compile job failed: {}, params: {:?}", e, params);
+        return false;
+    }
+    info!("compile job requeued, qid: {}", params.qid);
+    let reported = texhub_client()
+        .update_job_state(params.user_id, params.qid, &CompileJobState::Queued)
+        .await;
+    if let Err(e) = reported {
+        error!(
+            "report requeued compile job failed: {}, qid: {}",
+            e, params.qid
+        );
+    }
+    return true;
+}
+
+async fn xadd_compile_record(params: &CompileAppParams) -> RedisResult<String> {
+    let settings = app_settings();
+    let mut con = shared_redis().await?;
+    let fields: Vec<(&str, String)> = vec![
+        ("file_path", params.file_path.clone()),
+        ("out_path", params.out_path.clone()),
+        ("project_id", params.project_id.clone()),
+        ("req_time", params.req_time.to_string()),
+        ("qid", params.qid.to_string()),
+        ("version_no", params.version_no.clone()),
+        ("log_file_name", params.log_file_name.clone()),
+        ("proj_created_time", params.proj_created_time.to_string()),
+        ("isolated", params.isolated.to_string()),
+        ("user_id", params.user_id.to_string()),
+        ("priority", params.priority.name().to_owned()),
+    ];
+    return redis::cmd("XADD")
+        .arg(&settings.cv.compile_stream_redis_key)
+        .arg("*")
+        .arg(&fields)
+        .query_async(&mut con)
+        .await;
+}
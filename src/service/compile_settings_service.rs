@@ -0,0 +1,51 @@
+use crate::{
+    model::project::compile_settings::{CompileMode, CompileSettings},
+    rest::user::config::config_fetcher::get_one_user_config_cached,
+};
+use log::warn;
+
+/// the user config keys of the compile, see docs/api/user/user-conf.md
+pub const COMPILE_MODE_KEY: &str = "COMPILE_MODE";
+pub const COMPILE_ENGINE_KEY: &str = "COMPILE_ENGINE";
+pub const COMPILE_DRAFT_KEY: &str = "COMPILE_DRAFT";
+pub const COMPILE_TIMEOUT_KEY: &str = "COMPILE_TIMEOUT";
+
+async fn user_config_value(user_id: i64, key: &str) -> Option<String> {
+    let conf = get_one_user_config_cached(user_id, key).await?;
+    let value = conf.config_value.trim().to_owned();
+    if value.is_empty() {
+        return None;
+    }
+    return Some(value);
+}
+
+/**
+ * resolve the compile settings from the user config, the missing one use the server default
+ */
+pub async fn resolve_compile_settings(user_id: i64) -> CompileSettings {
+    let mut settings = CompileSettings::default();
+    if user_id <= 0 {
+        return settings;
+    }
+    if let Some(mode) = user_config_value(user_id, COMPILE_MODE_KEY).await {
+        settings.mode = CompileMode::from_name(&mode);
+    }
+    if let Some(engine) = user_config_value(user_id, COMPILE_ENGINE_KEY).await {
+        settings.engine = engine.to_lowercase();
+        if settings.engine_name() != settings.engine {
+            warn!(
+                "unsupported compile engine {}, user_id: {}, fallback to {}",
+                engine,
+                user_id,
+                settings.engine_name()
+            );
+        }
+    }
+    if let Some(draft) = user_config_value(user_id, COMPILE_DRAFT_KEY).await {
+        settings.draft = draft == "1" || draft.eq_ignore_ascii_case("true");
+    }
+    if let Some(timeout) = user_config_value(user_id, COMPILE_TIMEOUT_KEY).await {
+        settings.timeout_secs = timeout.parse::<u64>().unwrap_or(0);
+    }
+    return settings;
+}
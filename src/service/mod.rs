@@ -1,2 +1,7 @@
 pub mod project_service;
-pub mod global;
\ No newline at end of file
+pub mod global;
+pub mod compile_log_service;
+pub mod compile_cancel_service;
+pub mod compile_settings_service;
+pub mod readiness_service;
+pub mod compile_requeue_service;
\ No newline at end of file
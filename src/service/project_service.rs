@@ -12,6 +12,7 @@ use crate::{
         synctex_scanner_free, synctex_scanner_get_name, synctex_scanner_new_with_output_file,
         synctex_scanner_next_result,
     },
+    common::metrics::app_metrics::synctex_query_timer,
     model::{
         request::proj::{get_pdf_pos_params::GetPdfPosParams, get_src_pos_params::GetSrcPosParams},
         response::proj::{pdf_pos_resp::PdfPosResp, src_pos_resp::SrcPosResp},
@@ -24,6 +25,7 @@ use std::path::Path;
 
 pub fn get_pdf_pos(params: &GetPdfPosParams) -> Vec<PdfPosResp> {
     info!("get pdf pos params:{:?}", params);
+    let _timer = synctex_query_timer("pdf_pos");
     let proj_dir = get_proj_base_dir(&params.project_id, params.created_time);
     let pdf_file_name = format!("{}{}", get_filename_without_ext(&params.main_file), ".pdf");
     let full_pdf_file_path = join_paths(&[&proj_dir, &pdf_file_name.to_string()]);
@@ -80,6 +82,7 @@ pub fn get_pdf_pos(params: &GetPdfPosParams) -> Vec<PdfPosResp> {
 }
 
 pub fn get_src_pos(params: &GetSrcPosParams) -> Vec<SrcPosResp> {
+    let _timer = synctex_query_timer("src_pos");
     let proj_dir = get_proj_base_dir(&params.project_id, params.create_time);
     let pdf_file_name = format!("{}{}", get_filename_without_ext(&params.main_file), ".pdf");
     let file_path = join_paths(&[&proj_dir, &pdf_file_name.to_string()]);
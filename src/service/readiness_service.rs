@@ -0,0 +1,207 @@
+use crate::{
+    common::{
+        cache::redis_pool::redis_con, metrics::app_metrics::app_metrics,
+        settings::app_settings::Settings,
+    },
+    model::{
+        project::compile_settings::SUPPORTED_ENGINES,
+        response::monitor::readiness_resp::{ReadinessCheck, ReadinessResp},
+    },
+    task::app_shutdown::is_shutting_down,
+    util::fs_util::ensure_writable_dir,
+};
+use log::warn;
+use std::{
+    process::Command,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// the unix seconds of the last consumer loop round, 0 when it never ran
+static CONSUMER_HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+/// the crash reason while the consumer waits for the restart
+static CONSUMER_DOWN: Mutex<Option<String>> = Mutex::new(None);
+static CONSUMER_RESTARTS: AtomicU64 = AtomicU64::new(0);
+
+fn now_secs() -> u64 {
+    return SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+}
+
+/**
+ * called by the compile stream consumer on every loop round, the lock wait
+ * and between the redis and texhub calls of one record
+ */
+pub fn record_consumer_heartbeat() {
+    CONSUMER_HEARTBEAT.store(now_secs(), Ordering::Relaxed);
+}
+
+/**
+ * called by the consumer supervisor when the consumer (re)started
+ */
+pub fn record_consumer_up() {
+    if let Ok(mut down) = CONSUMER_DOWN.lock() {
+        *down = None;
+    }
+    app_metrics().compile_consumer_up.set(1);
+}
+
+/**
+ * called by the consumer supervisor when the consumer crashed, the readiness fails until it restarted
+ */
+pub fn record_consumer_down(reason: &str) {
+    if let Ok(mut down) = CONSUMER_DOWN.lock() {
+        *down = Some(reason.to_owned());
+    }
+    CONSUMER_RESTARTS.fetch_add(1, Ordering::Relaxed);
+    app_metrics().compile_consumer_up.set(0);
+    app_metrics().compile_consumer_restarts.inc();
+}
+
+/**
+ * run every readiness check, it blocks on the engine and the redis calls
+ */
+pub fn check_readiness(settings: &Settings) -> ReadinessResp {
+    let mut checks: Vec<ReadinessCheck> = Vec::new();
+    for engine in SUPPORTED_ENGINES {
+        checks.push(check_engine(engine));
+    }
+    for font in settings.required_fonts() {
+        checks.push(check_font(&font));
+    }
+    checks.push(check_redis());
+    for dir in [
+        &settings.cv.texhub_proj_compile_base_dir,
+        &settings.cv.cv_compile_base_dir,
+    ] {
+        checks.push(check_compile_dir(dir, settings.cv.readiness_min_free_mb));
+    }
+    if is_shutting_down() {
+        checks.push(ReadinessCheck::fail(
+            "shutdown",
+            "draining the compile jobs".to_owned(),
+        ));
+    }
+    checks.push(check_consumer_heartbeat(
+        settings.cv.readiness_heartbeat_stale_secs,
+    ));
+    let resp = ReadinessResp::from_checks(checks);
+    if !resp.is_ready() {
+        warn!("readiness degraded: {:?}", resp);
+    }
+    return resp;
+}
+
+fn check_engine(engine: &str) -> ReadinessCheck {
+    let name = format!("{}:{}", "engine", engine);
+    match Command::new(engine).arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_owned();
+            ReadinessCheck::pass(&name, version)
+        }
+        Ok(output) => ReadinessCheck::fail(&name, format!("exit status: {}", output.status)),
+        Err(e) => ReadinessCheck::fail(&name, format!("not runnable: {}", e)),
+    }
+}
+
+/**
+ * fc-match always return a fallback font, list the family to make sure it was installed
+ * the font dirs of the tex distribution were added by config/tex-conf/local.conf
+ */
+fn check_font(family: &str) -> ReadinessCheck {
+    let name = format!("{}:{}", "font", family);
+    match Command::new("fc-list").arg(family).arg("file").output() {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            match stdout.lines().next() {
+                Some(file) => ReadinessCheck::pass(&name, file.trim().to_owned()),
+                None => ReadinessCheck::fail(&name, "not found by fontconfig".to_owned()),
+            }
+        }
+        Ok(output) => {
+            ReadinessCheck::fail(&name, format!("fc-list exit status: {}", output.status))
+        }
+        Err(e) => ReadinessCheck::fail(&name, format!("fc-list not runnable: {}", e)),
+    }
+}
+
+fn check_redis() -> ReadinessCheck {
+    let pong: Result<String, String> = redis_con().and_then(|mut con| {
+        redis::cmd("PING")
+            .query(&mut *con)
+            .map_err(|e| e.to_string())
+    });
+    match pong {
+        Ok(p) => ReadinessCheck::pass("redis", p),
+        Err(e) => ReadinessCheck::fail("redis", e.to_string()),
+    }
+}
+
+/**
+ * the compile dir should be writable and keep the free space for the project output
+ */
+fn check_compile_dir(dir: &str, min_free_mb: u64) -> ReadinessCheck {
+    let name = format!("{}:{}", "dir", dir);
+    if let Err(e) = ensure_writable_dir(dir) {
+        return ReadinessCheck::fail(&name, e);
+    }
+    match free_space_mb(dir) {
+        Some(free) if free >= min_free_mb => {
+            ReadinessCheck::pass(&name, format!("free {}MB", free))
+        }
+        Some(free) => ReadinessCheck::fail(
+            &name,
+            format!("free {}MB less than {}MB", free, min_free_mb),
+        ),
+        None => ReadinessCheck::fail(&name, "read the free space failed".to_owned()),
+    }
+}
+
+/// the available space of the filesystem holding the dir, read from the posix df output
+fn free_space_mb(dir: &str) -> Option<u64> {
+    let output = Command::new("df").arg("-Pk").arg(dir).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let available_kb = stdout
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse::<u64>()
+        .ok()?;
+    return Some(available_kb / 1024);
+}
+
+fn check_consumer_heartbeat(stale_secs: u64) -> ReadinessCheck {
+    let down = CONSUMER_DOWN.lock().map(|d| d.clone()).unwrap_or_default();
+    if let Some(reason) = down {
+        return ReadinessCheck::fail(
+            "consumer",
+            format!(
+                "down: {}, restarts: {}",
+                reason,
+                CONSUMER_RESTARTS.load(Ordering::Relaxed)
+            ),
+        );
+    }
+    let last = CONSUMER_HEARTBEAT.load(Ordering::Relaxed);
+    if last == 0 {
+        return ReadinessCheck::fail("consumer", "no heartbeat yet".to_owned());
+    }
+    let age = now_secs().saturating_sub(last);
+    if age > stale_secs {
+        return ReadinessCheck::fail("consumer", format!("last heartbeat {}s ago", age));
+    }
+    return ReadinessCheck::pass("consumer", format!("last heartbeat {}s ago", age));
+}
@@ -1,16 +1,25 @@
 use super::{
-    compile_task_consumer::consume_redis_stream,
+    compile_consumer_supervisor::supervise_compile_consumer,
     texhub::compile::check_expire_compile_task::check_expired_queue_task,
 };
+use crate::common::settings::app_settings::app_settings;
+use crate::common::trace::trace_init::init_tracing;
+use crate::task::app_shutdown::is_shutting_down;
 use log::{error, info};
 use tokio::spawn;
-use tokio::task::spawn_blocking;
 use tokio_cron_scheduler::{Job, JobScheduler};
 
 pub fn init_logging() -> Result<(), Box<dyn std::error::Error>> {
     std::fs::create_dir_all("log")?;
-    log4rs::init_file("log4rs.yaml", Default::default())?;
-    info!("log4rs initialized successfully");
+    // human | json, the json lines carry the per-job fields in the mdc object
+    let log_format = app_settings().cv.log_format.clone();
+    let log_config = if log_format == "json" {
+        "log4rs-json.yaml"
+    } else {
+        "log4rs.yaml"
+    };
+    log4rs::init_file(log_config, Default::default())?;
+    info!("log4rs initialized successfully, config: {}", log_config);
     Ok(())
 }
 
@@ -19,20 +28,18 @@ pub async fn initial_task() -> Result<(), Box<dyn std::error::Error>> {
         error!("Failed to initialize logging: {}", e);
         return Ok(());
     }
+    init_tracing();
 
-    // 在独立线程中运行定时任务
-    std::thread::spawn(|| {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        rt.block_on(async {
-            if let Err(e) = initial_task_in_thread().await {
-                error!("Failed to initialize scheduled tasks: {}", e);
-            }
-        });
+    // 定时任务和编译任务共用主运行时
+    spawn(async {
+        if let Err(e) = initial_task_in_thread().await {
+            error!("Failed to initialize scheduled tasks: {}", e);
+        }
     });
 
-    // 启动 Redis stream 消费者
+    // 启动 Redis stream 消费者，崩溃后退避重启
     spawn(async {
-        consume_redis_stream().await;
+        supervise_compile_consumer().await;
     });
 
     Ok(())
@@ -43,31 +50,28 @@ pub async fn initial_task_in_thread() -> Result<(), Box<dyn std::error::Error>>
 
     // Add async job
     sched
-        .add(Job::new_async("1/45 * * * * *", |_uuid, _l| {
-            Box::pin(async move {
-                check_expired_queue_task().await;
-            })
-        })?)
+        .add(Job::new_async(
+            app_settings().cv.expire_check_cron.as_str(),
+            |_uuid, _l| {
+                Box::pin(async move {
+                    check_expired_queue_task().await;
+                })
+            },
+        )?)
         .await?;
 
     // Start the scheduler
     sched.start().await?;
     info!("Job scheduler started successfully");
 
-    // 在后台运行调度器，保持其生命周期
-    tokio::spawn(async move {
-        // 保持主任务运行，防止程序退出
-        if let Err(e) = tokio::signal::ctrl_c().await {
-            error!("Failed to wait for ctrl_c: {}", e);
-        }
-        // 清理
-        if let Err(e) = sched.shutdown().await {
-            error!("Failed to shutdown scheduler: {}", e);
-        }
-    });
-
-    // 无限循环保持线程活跃
-    loop {
+    // 保持线程活跃，直到进程开始优雅退出
+    while !is_shutting_down() {
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
     }
+    // 清理
+    if let Err(e) = sched.shutdown().await {
+        error!("Failed to shutdown scheduler: {}", e);
+    }
+    info!("Job scheduler stopped");
+    Ok(())
 }
@@ -0,0 +1,101 @@
+use crate::{
+    common::settings::app_settings::app_settings,
+    task::texhub::compile::compile_scheduler::{
+        compile_jobs_running, requeue_pending_compile_jobs,
+    },
+};
+use actix_web::dev::ServerHandle;
+use log::{error, info, warn};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+/// the cancel reason of the compile interrupted by the shutdown, the job was put back to the stream
+pub const REQUEUE_REASON: &str = "requeued";
+/// the interrupted engine sees the flag in the cancel check interval
+const INTERRUPT_GRACE: Duration = Duration::from_secs(5);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+static COMPILE_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/**
+ * the worker stops taking the new compile jobs
+ */
+pub fn is_shutting_down() -> bool {
+    return SHUTTING_DOWN.load(Ordering::SeqCst);
+}
+
+/**
+ * the running compile could not finish before the drain deadline and should stop now
+ */
+pub fn compile_interrupted() -> bool {
+    return COMPILE_INTERRUPTED.load(Ordering::SeqCst);
+}
+
+/**
+ * wait for the SIGTERM from kubernetes or the ctrl-c
+ */
+pub async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut sigterm = match signal(SignalKind::terminate()) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("listen SIGTERM failed: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+        tokio::select! {
+            _ = sigterm.recv() => info!("SIGTERM received"),
+            _ = tokio::signal::ctrl_c() => info!("ctrl-c received"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        info!("ctrl-c received");
+    }
+}
+
+/**
+ * stop pulling the stream, requeue the waiting jobs and let the running compiles finish
+ * the compiles still running after `cv.shutdown_drain_secs` were interrupted and requeued
+ * then the http server stops
+ */
+pub async fn graceful_shutdown(server: ServerHandle) {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+    info!("graceful shutdown started");
+    requeue_pending_compile_jobs().await;
+    let drain_deadline = Duration::from_secs(app_settings().cv.shutdown_drain_secs);
+    if !wait_compile_jobs_finished(drain_deadline).await {
+        warn!(
+            "compile jobs still running after {}s, interrupt and requeue them, running: {}",
+            drain_deadline.as_secs(),
+            compile_jobs_running()
+        );
+        COMPILE_INTERRUPTED.store(true, Ordering::SeqCst);
+        if !wait_compile_jobs_finished(INTERRUPT_GRACE).await {
+            error!(
+                "compile jobs did not stop after the interrupt, running: {}",
+                compile_jobs_running()
+            );
+        }
+    }
+    server.stop(true).await;
+    info!("graceful shutdown finished");
+}
+
+async fn wait_compile_jobs_finished(deadline: Duration) -> bool {
+    let started = Instant::now();
+    while compile_jobs_running() > 0 {
+        if started.elapsed() >= deadline {
+            return false;
+        }
+        sleep(Duration::from_millis(500)).await;
+    }
+    return true;
+}
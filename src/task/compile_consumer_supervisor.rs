@@ -0,0 +1,49 @@
+use super::{app_shutdown::is_shutting_down, compile_task_consumer::consume_redis_stream};
+use crate::{
+    common::settings::app_settings::app_settings,
+    service::readiness_service::{record_consumer_down, record_consumer_up},
+};
+use log::{error, info};
+use std::time::{Duration, Instant};
+use tokio::{spawn, time::sleep};
+
+/// the first restart waits this long, the wait doubles after every crash
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// the consumer ran this long was treated as recovered, the next crash starts from the initial wait
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+/**
+ * keep the compile stream consumer running, restart it with the exponential backoff
+ * when it returned the redis error or panicked, stop when the worker shuts down
+ */
+pub async fn supervise_compile_consumer() {
+    let max_backoff = Duration::from_secs(app_settings().cv.consumer_backoff_max_secs);
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        if is_shutting_down() {
+            return;
+        }
+        record_consumer_up();
+        let started = Instant::now();
+        // run in its own task, the panic was caught by the join handle
+        let reason = match spawn(consume_redis_stream()).await {
+            Ok(Ok(())) => {
+                info!("compile stream consumer stopped");
+                return;
+            }
+            Ok(Err(e)) => e,
+            Err(e) => format!("consumer task failed: {}", e),
+        };
+        if started.elapsed() >= HEALTHY_RUN {
+            backoff = INITIAL_BACKOFF;
+        }
+        error!(
+            "compile stream consumer crashed: {}, restart in {}s",
+            reason,
+            backoff.as_secs()
+        );
+        record_consumer_down(&reason);
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(max_backoff);
+    }
+}
@@ -1,36 +1,86 @@
-use std::{env, io};
+use std::io;
 
+use crate::common::settings::app_settings::app_settings;
 use crate::render::texhub::pipeline::pipeline_render_works::del_redis_stream;
 use crate::{
-    controller::tex::tex_controller::compile_tex_from_mq,
-    model::project::compile_app_params::CompileAppParams,
-    rest::client::cv_client::update_queue_status,
+    common::{
+        cache::{
+            redis_async::dedicated_redis,
+            redis_lock::{try_redis_lock, RedisLockGuard},
+        },
+        logging::job_log_context::JobLogContext,
+        metrics::app_metrics::observe_compile_queue_lag,
+        trace::trace_propagation::capture_trace_context,
+    },
+    model::project::{
+        compile_app_params::CompileAppParams, compile_priority::CompilePriority,
+        compile_settings::CompileSettings,
+    },
+    rest::client::texhub_client::texhub_client,
+    service::{
+        compile_cancel_service::{record_job_owner, supersede_older_job},
+        compile_settings_service::resolve_compile_settings,
+        readiness_service::record_consumer_heartbeat,
+    },
+    task::{
+        app_shutdown::is_shutting_down,
+        texhub::compile::compile_scheduler::{compile_scheduler_has_capacity, submit_compile_job},
+    },
 };
-use log::{error, warn};
+use log::{error, info, warn};
 use redis::streams::{StreamId, StreamKey, StreamReadOptions, StreamReadReply};
-use redis::Commands;
-use redlock::{Lock, RedLock};
-use rust_wheel::config::{
-    app::app_conf_reader::get_app_config,
-    cache::redis_util::{delete_stream_element, get_con},
-};
-use std::net::ToSocketAddrs;
+use redis::{aio::MultiplexedConnection, AsyncCommands, RedisResult};
+use std::{collections::HashMap, net::ToSocketAddrs, str::FromStr, time::Duration};
+use tokio::time::sleep;
+use tracing::{info_span, Instrument};
+
+/// another worker was reading the stream, wait before the next lock attempt
+const STREAM_LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+/// the XREAD BLOCK of one round, the record was read and deleted while holding the stream lock
+const STREAM_READ_BLOCK_MILLIS: usize = 1000;
+/// well beyond the read block plus the XDEL, the lock never expires before the record was deleted
+const STREAM_LOCK_TTL_MILLIS: usize = 5000;
+/// the worker was full, wait for a running job to finish before reading the stream
+const CAPACITY_WAIT_INTERVAL: Duration = Duration::from_millis(200);
 
-pub async fn consume_redis_stream() {
-    let mut con = get_con();
-    let stream_key = get_app_config("cv.compile_stream_redis_key");
-    let redis_conn_str = env::var("REDIS_URL").unwrap();
+/**
+ * read the compile stream until the worker shuts down
+ * the redis error was returned, the supervisor restarts the consumer with a new connection
+ */
+pub async fn consume_redis_stream() -> Result<(), String> {
+    // the XREAD BLOCK holds the connection, it was not shared with the compile jobs
+    let mut con = dedicated_redis()
+        .await
+        .map_err(|e| format!("connect redis failed: {}", e))?;
+    let stream_key = app_settings().cv.compile_stream_redis_key.clone();
+    let redis_conn_str = app_settings().redis_url.clone();
     let stream_id = "0";
     loop {
-        let rl = RedLock::new(vec![redis_conn_str.as_str()]);
-        let lock;
+        if is_shutting_down() {
+            info!("stop consuming the compile stream, the worker is shutting down");
+            return Ok(());
+        }
+        record_consumer_heartbeat();
+        if !compile_scheduler_has_capacity() {
+            // leave the records in the stream to the other workers
+            sleep(CAPACITY_WAIT_INTERVAL).await;
+            continue;
+        }
+        let mut lock;
         loop {
-            match rl.lock("mutex".as_bytes(), 1000) {
+            match try_redis_lock("mutex", STREAM_LOCK_TTL_MILLIS).await {
                 Ok(Some(l)) => {
                     lock = l;
                     break;
                 }
-                Ok(None) => (),
+                Ok(None) => {
+                    if is_shutting_down() {
+                        return Ok(());
+                    }
+                    // waiting for the other workers was alive too
+                    record_consumer_heartbeat();
+                    sleep(STREAM_LOCK_RETRY_INTERVAL).await;
+                }
                 Err(e) => {
                     error!(
                         "consume_redis_stream Error communicating with redis: {}, conn str: {}",
@@ -39,26 +89,31 @@ pub async fn consume_redis_stream() {
                     check_dns(&redis_conn_str);
                     check_dns("infra-server-service.reddwarf-pro.svc.cluster.local");
                     check_dns("kubernetes.default");
+                    return Err(format!("lock the compile stream failed: {}", e));
                 }
             }
         }
-        let options = StreamReadOptions::default().count(1).block(1000).noack();
-        let result = con.xread_options(&[stream_key.as_str()], &[stream_id], &options);
-        if let Err(e) = result.as_ref() {
-            error!("read stream failed: {}", e);
-            break;
-        }
-        let stream_reply: StreamReadReply = result.unwrap();
-        for sk in stream_reply.clone().keys {
-            match sk.key.as_str() {
-                "texhub-server:proj:s-comp-queue" => {
-                    handle_proj_compile_stream(sk, &rl, &lock).await;
-                }
-                _ => {
-                    error!("not implement");
-                }
+        let options = StreamReadOptions::default()
+            .count(1)
+            .block(STREAM_READ_BLOCK_MILLIS)
+            .noack();
+        let result: RedisResult<StreamReadReply> = con
+            .xread_options(&[stream_key.as_str()], &[stream_id], &options)
+            .await;
+        record_consumer_heartbeat();
+        let stream_reply = match result {
+            Ok(r) => r,
+            Err(e) => {
+                lock.release().await;
+                return Err(format!("read stream failed: {}", e));
             }
+        };
+        // only `cv.compile_stream_redis_key` was read, every key was the compile stream
+        for sk in stream_reply.keys {
+            handle_proj_compile_stream(sk, &mut lock, &mut con).await;
         }
+        // release when nothing was read, the handled record released it already
+        lock.release().await;
     }
 }
 
@@ -80,70 +135,156 @@ fn check_dns(host: &str) {
     }
 }
 
-async fn handle_proj_compile_stream(sk: StreamKey, rl: &RedLock, lock: &Lock<'_>) {
+async fn handle_proj_compile_stream(
+    sk: StreamKey,
+    lock: &mut RedisLockGuard,
+    con: &mut MultiplexedConnection,
+) {
     for stream_id in sk.clone().ids {
-        handle_proj_compile_record(stream_id, rl, lock, &sk).await;
+        handle_proj_compile_record(stream_id, lock, &sk, con).await;
     }
 }
 
 async fn handle_proj_compile_record(
     stream_id: StreamId,
-    rl: &RedLock,
-    lock: &Lock<'_>,
+    lock: &mut RedisLockGuard,
     sk: &StreamKey,
+    con: &mut MultiplexedConnection,
 ) {
-    let param: CompileAppParams = do_task(&stream_id);
-    let redis_url = env::var("REDIS_URL").unwrap();
-    let client = redis::Client::open(redis_url.as_str()).unwrap();
-    let mut con = client.get_connection().unwrap();
-    del_redis_stream(&param, &mut con);
-    let u_result = update_queue_status(1, &param.qid, Some(-1)).await;
-    if !u_result {
-        // do not return when update failed
-        // it will make the redis stream retry and go into a dead loop
-        error!("update status failed: {},sk:{:?}", u_result, stream_id);
-    }
-    let del_result = delete_stream_element(sk.key.as_str(), stream_id.id.clone());
-    if let Err(e) = del_result {
-        error!("delete stream failed: {}, stream id: {:?}", e, &stream_id);
-        rl.unlock(&lock);
+    let param: CompileAppParams = match do_task(&stream_id) {
+        Ok(p) => p,
+        Err(e) => {
+            // drop the broken record, it would crash the consumer on every read
+            error!("invalid compile record: {}, stream id: {:?}", e, stream_id);
+            delete_compile_record(con, sk, &stream_id).await;
+            lock.release().await;
+            return;
+        }
+    };
+    let span = info_span!(
+        "compile.stream_read",
+        project_id = %param.project_id,
+        qid = param.qid,
+        stream_id = %stream_id.id
+    );
+    let log_context = JobLogContext::of(&param);
+    log_context
+        .scope(accept_proj_compile_record(param, stream_id, lock, sk, con))
+        .instrument(span)
+        .await;
+}
+
+async fn accept_proj_compile_record(
+    mut param: CompileAppParams,
+    stream_id: StreamId,
+    lock: &mut RedisLockGuard,
+    sk: &StreamKey,
+    con: &mut MultiplexedConnection,
+) {
+    observe_compile_queue_lag(param.req_time, param.priority.name());
+    let deleted = delete_compile_record(con, sk, &stream_id).await;
+    // the other workers read the next record while this one was reported to texhub
+    lock.release().await;
+    if !deleted {
         return;
     }
-    rl.unlock(&lock);
-    compile_tex_from_mq(param).await;
+    del_redis_stream(&param, con).await;
+    let u_result = texhub_client()
+        .update_queue_status(param.user_id, param.qid, 1, -1)
+        .instrument(info_span!("texhub.update_queue_status", qid = param.qid))
+        .await;
+    match u_result {
+        Ok(queue) => {
+            // the older texhub server did not put the user id on the stream record
+            if param.user_id == 0 {
+                param.user_id = queue.user_id;
+            }
+        }
+        Err(e) => {
+            // the record was deleted already, the job runs even the status was not updated
+            error!("update status failed: {}, sk:{:?}", e, stream_id);
+        }
+    }
+    record_consumer_heartbeat();
+    if param.user_id != 0 {
+        record_job_owner(param.qid, param.user_id).await;
+    }
+    param.settings = resolve_compile_settings(param.user_id).await;
+    // only the newest compile of the project was worth running
+    if !param.isolated {
+        supersede_older_job(&param.project_id, param.qid).await;
+    }
+    record_consumer_heartbeat();
+    param.trace_context = capture_trace_context();
+    submit_compile_job(param).await;
 }
 
-fn do_task(stream_id: &StreamId) -> CompileAppParams {
-    let fp_value: &redis::Value = stream_id.map.get("file_path").unwrap();
-    let file_path = extract_string_value(fp_value);
-    if file_path.is_none() {
-        error!("read the file path is None {:?}", stream_id);
+async fn delete_compile_record(
+    con: &mut MultiplexedConnection,
+    sk: &StreamKey,
+    stream_id: &StreamId,
+) -> bool {
+    let del_result: RedisResult<i64> = con.xdel(sk.key.as_str(), &[stream_id.id.as_str()]).await;
+    if let Err(e) = del_result {
+        error!("delete stream failed: {}, stream id: {:?}", e, stream_id);
+        return false;
     }
-    let op_value: &redis::Value = stream_id.map.get("out_path").unwrap();
-    let out_path = extract_string_value(op_value);
-    let pi_value: &redis::Value = stream_id.map.get("project_id").unwrap();
-    let project_id = extract_string_value(pi_value);
-    let rt_value: &redis::Value = stream_id.map.get("req_time").unwrap();
-    let req_time = extract_string_value(rt_value);
-    let qid_value: &redis::Value = stream_id.map.get("qid").unwrap();
-    let qid = extract_string_value(qid_value);
-    let vn_value: &redis::Value = stream_id.map.get("version_no").unwrap();
-    let version_no = extract_string_value(vn_value);
-    let log_file_value: &redis::Value = stream_id.map.get("log_file_name").unwrap();
-    let log_file_name = extract_string_value(log_file_value);
-    let created_time_value: &redis::Value = stream_id.map.get("proj_created_time").unwrap();
-    let proj_created_time = extract_string_value(created_time_value);
+    return true;
+}
+
+fn do_task(stream_id: &StreamId) -> Result<CompileAppParams, String> {
+    // optional, the older texhub server did not send it
+    let isolated = stream_id
+        .map
+        .get("isolated")
+        .and_then(extract_string_value)
+        .map(|v| v == "1" || v == "true")
+        .unwrap_or(false);
+    let user_id = stream_id
+        .map
+        .get("user_id")
+        .and_then(extract_string_value)
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0);
+    // interactive | batch | cv
+    let priority = stream_id
+        .map
+        .get("priority")
+        .and_then(extract_string_value)
+        .map(|v| CompilePriority::from_name(&v))
+        .unwrap_or_default();
     let param: CompileAppParams = CompileAppParams {
-        file_path: file_path.unwrap(),
-        out_path: out_path.unwrap(),
-        project_id: project_id.unwrap(),
-        req_time: req_time.unwrap().parse().unwrap(),
-        qid: qid.unwrap().parse().unwrap(),
-        version_no: version_no.unwrap(),
-        log_file_name: log_file_name.unwrap(),
-        proj_created_time: proj_created_time.unwrap().parse().unwrap(),
+        file_path: required_field(stream_id, "file_path")?,
+        out_path: required_field(stream_id, "out_path")?,
+        project_id: required_field(stream_id, "project_id")?,
+        req_time: required_number(stream_id, "req_time")?,
+        qid: required_number(stream_id, "qid")?,
+        version_no: required_field(stream_id, "version_no")?,
+        log_file_name: required_field(stream_id, "log_file_name")?,
+        proj_created_time: required_number(stream_id, "proj_created_time")?,
+        isolated: isolated,
+        user_id: user_id,
+        priority: priority,
+        // resolved from the user config after the record was acked
+        settings: CompileSettings::default(),
+        trace_context: HashMap::new(),
     };
-    return param;
+    return Ok(param);
+}
+
+fn required_field(stream_id: &StreamId, name: &str) -> Result<String, String> {
+    return stream_id
+        .map
+        .get(name)
+        .and_then(extract_string_value)
+        .ok_or_else(|| format!("{} {}", "missing field", name));
+}
+
+fn required_number<T: FromStr>(stream_id: &StreamId, name: &str) -> Result<T, String> {
+    let value = required_field(stream_id, name)?;
+    return value
+        .parse::<T>()
+        .map_err(|_| format!("{} {}: {}", "invalid number field", name, value));
 }
 
 fn extract_string_value(value: &redis::Value) -> Option<String> {
@@ -1,4 +1,6 @@
 pub mod compile_task_consumer;
 pub mod gen_cv_worker;
 pub mod app_init;
-pub mod texhub;
+pub mod app_shutdown;
+pub mod compile_consumer_supervisor;
+pub mod texhub;
\ No newline at end of file
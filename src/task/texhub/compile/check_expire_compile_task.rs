@@ -1,11 +1,12 @@
-use crate::rest::client::texhub_queue_client::update_expired_job;
+use crate::rest::client::texhub_client::texhub_client;
 use std::time::Duration;
-use log::{info, warn};
+use log::{error, info, warn};
 use tokio::time;
 
 pub async fn get_expired_queue_task() {
-    let cv = update_expired_job();
-    cv.await;
+    if let Err(e) = texhub_client().expire_check().await {
+        error!("check expired compile queue failed: {}", e);
+    }
     // make other task could be invoke
     tokio::task::yield_now().await;
 }
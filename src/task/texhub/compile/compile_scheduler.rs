@@ -0,0 +1,443 @@
+use crate::{
+    common::{
+        logging::job_log_context::JobLogContext,
+        metrics::app_metrics::app_metrics,
+        settings::app_settings::{app_settings, CvSettings},
+        trace::trace_propagation::attach_trace_context,
+    },
+    model::project::{compile_app_params::CompileAppParams, compile_priority::CompilePriority},
+    render::texhub::pipeline::{
+        compile_job::fail_unstarted_job, compile_mode_router::compile_texhub_project,
+    },
+    service::compile_requeue_service::requeue_compile_job,
+    task::app_shutdown::is_shutting_down,
+};
+use log::{info, warn};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Mutex, OnceLock},
+};
+use tracing::{info_span, Instrument};
+
+pub struct SchedulerConfig {
+    /// the compile jobs run at the same time in this worker
+    pub max_concurrent_jobs: usize,
+    /// the compile jobs of one user run at the same time, the others wait for the turn
+    pub max_user_concurrent_jobs: usize,
+    /// the jobs waiting for a slot, the consumer stops reading the stream when it was full
+    pub max_pending_jobs: usize,
+    /// the waiting jobs of one user, the job beyond it was rejected
+    pub max_user_pending_jobs: usize,
+}
+
+impl SchedulerConfig {
+    pub fn from_settings(cv: &CvSettings) -> Self {
+        return SchedulerConfig {
+            max_concurrent_jobs: cv.compile_max_concurrent_jobs.max(1),
+            max_user_concurrent_jobs: cv.compile_max_user_concurrent_jobs.max(1),
+            max_pending_jobs: cv.compile_max_pending_jobs.max(1),
+            max_user_pending_jobs: cv.compile_max_user_pending_jobs.max(1),
+        };
+    }
+}
+
+/**
+ * dispatch the compile jobs by priority, the users of the same priority take turns
+ * so one user's batch could not starve the others
+ */
+pub struct CompileScheduler {
+    config: SchedulerConfig,
+    /// the users in the round robin order with their pending jobs of each priority
+    pending: BTreeMap<CompilePriority, VecDeque<(i64, VecDeque<CompileAppParams>)>>,
+    running: HashMap<i64, usize>,
+    running_total: usize,
+}
+
+impl CompileScheduler {
+    pub fn new(config: SchedulerConfig) -> Self {
+        let mut pending = BTreeMap::new();
+        for priority in CompilePriority::all() {
+            pending.insert(priority, VecDeque::new());
+        }
+        return CompileScheduler {
+            config: config,
+            pending: pending,
+            running: HashMap::new(),
+            running_total: 0,
+        };
+    }
+
+    /**
+     * the job was given back when the user had too many waiting jobs
+     */
+    pub fn enqueue(&mut self, params: CompileAppParams) -> Result<(), CompileAppParams> {
+        if self.user_pending_len(params.user_id) >= self.config.max_user_pending_jobs {
+            return Err(params);
+        }
+        let users = self.pending.entry(params.priority).or_default();
+        match users.iter_mut().find(|(uid, _)| *uid == params.user_id) {
+            Some((_, jobs)) => jobs.push_back(params),
+            None => users.push_back((params.user_id, VecDeque::from([params]))),
+        }
+        return Ok(());
+    }
+
+    /**
+     * the worker could take one more job from the stream
+     * the jobs beyond it stay in the stream for the other workers
+     */
+    pub fn has_capacity(&self) -> bool {
+        let limit = self.config.max_concurrent_jobs + self.config.max_pending_jobs;
+        return self.running_total + self.pending_len() < limit;
+    }
+
+    /**
+     * take the next job to run, None when the worker is full or every waiting user reached the limit
+     */
+    pub fn next(&mut self) -> Option<CompileAppParams> {
+        if self.running_total >= self.config.max_concurrent_jobs {
+            return None;
+        }
+        for users in self.pending.values_mut() {
+            for _ in 0..users.len() {
+                let (uid, mut jobs) = match users.pop_front() {
+                    Some(u) => u,
+                    None => break,
+                };
+                let user_running = self.running.get(&uid).copied().unwrap_or(0);
+                if user_running >= self.config.max_user_concurrent_jobs {
+                    users.push_back((uid, jobs));
+                    continue;
+                }
+                let job = jobs.pop_front();
+                // move the user to the tail, the others of the same priority run first
+                if !jobs.is_empty() {
+                    users.push_back((uid, jobs));
+                }
+                if let Some(params) = job {
+                    *self.running.entry(uid).or_insert(0) += 1;
+                    self.running_total += 1;
+                    return Some(params);
+                }
+            }
+        }
+        return None;
+    }
+
+    pub fn finish(&mut self, user_id: i64) {
+        if let Some(count) = self.running.get_mut(&user_id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.running.remove(&user_id);
+            }
+        }
+        self.running_total = self.running_total.saturating_sub(1);
+    }
+
+    /**
+     * take all the waiting jobs out, for the requeue on shutdown
+     */
+    pub fn drain_pending(&mut self) -> Vec<CompileAppParams> {
+        let mut jobs: Vec<CompileAppParams> = Vec::new();
+        for users in self.pending.values_mut() {
+            for (_, user_jobs) in users.drain(..) {
+                jobs.extend(user_jobs);
+            }
+        }
+        return jobs;
+    }
+
+    pub fn pending_len(&self) -> usize {
+        return self
+            .pending
+            .values()
+            .flat_map(|users| users.iter())
+            .map(|(_, jobs)| jobs.len())
+            .sum();
+    }
+
+    fn user_pending_len(&self, user_id: i64) -> usize {
+        return self
+            .pending
+            .values()
+            .flat_map(|users| users.iter())
+            .filter(|(uid, _)| *uid == user_id)
+            .map(|(_, jobs)| jobs.len())
+            .sum();
+    }
+}
+
+fn scheduler() -> &'static Mutex<CompileScheduler> {
+    static SCHEDULER: OnceLock<Mutex<CompileScheduler>> = OnceLock::new();
+    SCHEDULER.get_or_init(|| {
+        Mutex::new(CompileScheduler::new(SchedulerConfig::from_settings(
+            &app_settings().cv,
+        )))
+    })
+}
+
+/**
+ * queue the compile job picked from the stream, it runs when the scheduler gives it a slot
+ */
+pub async fn submit_compile_job(params: CompileAppParams) {
+    if is_shutting_down() {
+        // picked just before the consumer stopped, leave it to the other workers
+        requeue_compile_job(&params).await;
+        return;
+    }
+    let rejected = {
+        let mut sched = scheduler().lock().unwrap();
+        let rejected = sched.enqueue(params).err();
+        app_metrics()
+            .compile_pending
+            .set(sched.pending_len() as i64);
+        info!("compile job queued, pending: {}", sched.pending_len());
+        rejected
+    };
+    if let Some(params) = rejected {
+        warn!(
+            "too many waiting compile jobs of the user, qid: {}, user_id: {}",
+            params.qid, params.user_id
+        );
+        fail_unstarted_job(
+            &params,
+            "Too many compile jobs waiting, please compile again later.",
+        )
+        .await;
+        return;
+    }
+    dispatch_compile_jobs();
+}
+
+/**
+ * the consumer reads the next record only when the worker could take it
+ */
+pub fn compile_scheduler_has_capacity() -> bool {
+    match scheduler().lock() {
+        Ok(sched) => sched.has_capacity(),
+        Err(e) => {
+            warn!("read compile scheduler capacity failed: {}", e);
+            false
+        }
+    }
+}
+
+/**
+ * the compile jobs running in this worker
+ */
+pub fn compile_jobs_running() -> usize {
+    match scheduler().lock() {
+        Ok(sched) => sched.running_total,
+        Err(e) => {
+            warn!("read running compile jobs failed: {}", e);
+            0
+        }
+    }
+}
+
+/**
+ * put the waiting jobs back to the compile stream when the worker shuts down
+ */
+pub async fn requeue_pending_compile_jobs() {
+    let jobs = {
+        let mut sched = scheduler().lock().unwrap();
+        let jobs = sched.drain_pending();
+        app_metrics().compile_pending.set(0);
+        jobs
+    };
+    info!("requeue the waiting compile jobs, count: {}", jobs.len());
+    for params in jobs {
+        requeue_compile_job(&params).await;
+    }
+}
+
+fn dispatch_compile_jobs() {
+    loop {
+        if is_shutting_down() {
+            return;
+        }
+        let next = {
+            let mut sched = scheduler().lock().unwrap();
+            let next = sched.next();
+            app_metrics()
+                .compile_pending
+                .set(sched.pending_len() as i64);
+            next
+        };
+        let params = match next {
+            Some(p) => p,
+            None => return,
+        };
+        app_metrics().compile_in_flight.inc();
+        info!(
+            "dispatch compile job, qid: {}, user_id: {}, priority: {:?}",
+            params.qid, params.user_id, params.priority
+        );
+        // the job was one future on the main runtime, the engine and the io were awaited
+        tokio::spawn(async move {
+            let _slot = RunningSlot {
+                user_id: params.user_id,
+            };
+            let span = info_span!(
+                "compile.job",
+                project_id = %params.project_id,
+                qid = params.qid,
+                priority = params.priority.name()
+            );
+            attach_trace_context(&span, &params.trace_context);
+            let compile_result = JobLogContext::of(&params)
+                .scope(compile_texhub_project(&params))
+                .instrument(span)
+                .await;
+            if compile_result.is_none() {
+                warn!("compile result is none, params:{:?}", params);
+            }
+        });
+    }
+}
+
+/**
+ * release the slot of the finished job, even the pipeline panics
+ */
+struct RunningSlot {
+    user_id: i64,
+}
+
+impl Drop for RunningSlot {
+    fn drop(&mut self) {
+        app_metrics().compile_in_flight.dec();
+        match scheduler().lock() {
+            Ok(mut sched) => sched.finish(self.user_id),
+            Err(e) => warn!("release compile slot failed: {}", e),
+        }
+        dispatch_compile_jobs();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SchedulerConfig {
+        return SchedulerConfig {
+            max_concurrent_jobs: 10,
+            max_user_concurrent_jobs: 10,
+            max_pending_jobs: 10,
+            max_user_pending_jobs: 10,
+        };
+    }
+
+    fn job(qid: i64, user_id: i64, priority: CompilePriority) -> CompileAppParams {
+        return CompileAppParams {
+            file_path: "".to_owned(),
+            out_path: "".to_owned(),
+            project_id: format!("{}{}", "proj-", qid),
+            req_time: 0,
+            qid: qid,
+            version_no: "".to_owned(),
+            log_file_name: "".to_owned(),
+            proj_created_time: 0,
+            isolated: false,
+            user_id: user_id,
+            priority: priority,
+            settings: Default::default(),
+            trace_context: HashMap::new(),
+        };
+    }
+
+    fn dispatch_all(sched: &mut CompileScheduler) -> Vec<i64> {
+        let mut qids = Vec::new();
+        while let Some(params) = sched.next() {
+            qids.push(params.qid);
+        }
+        return qids;
+    }
+
+    #[test]
+    fn next_dispatches_by_priority() {
+        let mut sched = CompileScheduler::new(config());
+        sched.enqueue(job(1, 1, CompilePriority::Batch)).unwrap();
+        sched.enqueue(job(2, 2, CompilePriority::Cv)).unwrap();
+        sched
+            .enqueue(job(3, 3, CompilePriority::Interactive))
+            .unwrap();
+        sched
+            .enqueue(job(4, 1, CompilePriority::Interactive))
+            .unwrap();
+        assert_eq!(dispatch_all(&mut sched), vec![3, 4, 2, 1]);
+    }
+
+    #[test]
+    fn next_takes_turns_between_users_of_the_same_priority() {
+        let mut sched = CompileScheduler::new(config());
+        for qid in [1, 2, 3] {
+            sched.enqueue(job(qid, 1, CompilePriority::Batch)).unwrap();
+        }
+        sched.enqueue(job(4, 2, CompilePriority::Batch)).unwrap();
+        sched.enqueue(job(5, 2, CompilePriority::Batch)).unwrap();
+        sched.enqueue(job(6, 3, CompilePriority::Batch)).unwrap();
+        assert_eq!(dispatch_all(&mut sched), vec![1, 4, 6, 2, 5, 3]);
+    }
+
+    #[test]
+    fn next_respects_the_running_limits() {
+        let mut sched = CompileScheduler::new(SchedulerConfig {
+            max_concurrent_jobs: 3,
+            max_user_concurrent_jobs: 1,
+            ..config()
+        });
+        for qid in [1, 2] {
+            sched
+                .enqueue(job(qid, 1, CompilePriority::Interactive))
+                .unwrap();
+        }
+        for qid in [3, 4, 5] {
+            sched
+                .enqueue(job(qid, qid, CompilePriority::Batch))
+                .unwrap();
+        }
+        // the second job of user 1 waits, the batch jobs of the others run
+        assert_eq!(dispatch_all(&mut sched), vec![1, 3, 4]);
+        sched.finish(3);
+        assert_eq!(dispatch_all(&mut sched), vec![5]);
+        sched.finish(1);
+        assert_eq!(dispatch_all(&mut sched), vec![2]);
+    }
+
+    #[test]
+    fn enqueue_rejects_beyond_the_user_pending_limit() {
+        let mut sched = CompileScheduler::new(SchedulerConfig {
+            max_user_pending_jobs: 2,
+            ..config()
+        });
+        sched
+            .enqueue(job(1, 1, CompilePriority::Interactive))
+            .unwrap();
+        sched.enqueue(job(2, 1, CompilePriority::Batch)).unwrap();
+        let rejected = sched.enqueue(job(3, 1, CompilePriority::Cv)).unwrap_err();
+        assert_eq!(rejected.qid, 3);
+        sched.enqueue(job(4, 2, CompilePriority::Cv)).unwrap();
+        assert_eq!(sched.pending_len(), 3);
+    }
+
+    #[test]
+    fn has_capacity_counts_the_running_and_pending_jobs() {
+        let mut sched = CompileScheduler::new(SchedulerConfig {
+            max_concurrent_jobs: 1,
+            max_pending_jobs: 1,
+            ..config()
+        });
+        assert!(sched.has_capacity());
+        sched
+            .enqueue(job(1, 1, CompilePriority::Interactive))
+            .unwrap();
+        assert!(sched.next().is_some());
+        assert!(sched.has_capacity());
+        sched
+            .enqueue(job(2, 2, CompilePriority::Interactive))
+            .unwrap();
+        assert!(!sched.has_capacity());
+        sched.finish(1);
+        assert!(sched.has_capacity());
+    }
+}
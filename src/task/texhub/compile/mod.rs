@@ -1 +1,2 @@
-pub mod check_expire_compile_task;
\ No newline at end of file
+pub mod check_expire_compile_task;
+pub mod compile_scheduler;
\ No newline at end of file
@@ -1,8 +1,93 @@
 use crate::model::cv::{
-    project::cv_project_resp::CvProjectResp, skill::cv_skill_resp::CvSkillResp,
-    work::cv_work_resp::CvWorkResp,
+    custom::cv_custom_section_resp::CvCustomSectionResp, cv_main::CvMainResp, edu::edu::CvEduResp,
+    lang::cv_lang_resp::CvLangResp, project::cv_project_resp::CvProjectResp,
+    skill::cv_skill_resp::CvSkillResp, work::cv_work_resp::CvWorkResp,
 };
 
+/**
+ * format the `yyyy-MM-dd` date to `yyyy.MM`
+ * keep the original value when the date could not be split
+ */
+pub fn format_month(date: &Option<String>) -> String {
+    let raw = date.as_deref().unwrap_or_default();
+    let parts: Vec<&str> = raw.split("-").collect();
+    if parts.len() < 2 {
+        return raw.to_owned();
+    }
+    return format!("{}.{}", parts[0], parts[1]);
+}
+
+/**
+ * the optional contact field, blank value treat as absent
+ */
+pub fn present_field(field: &Option<String>) -> Option<&str> {
+    return field.as_deref().map(|f| f.trim()).filter(|f| !f.is_empty());
+}
+
+/**
+ * the location and personal links in one line
+ * for the templates without dedicated contact macros
+ */
+pub fn get_contact_links_str(cv_main: &CvMainResp) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(location) = present_field(&cv_main.location) {
+        parts.push(location.to_owned());
+    }
+    for link in [&cv_main.homepage, &cv_main.blog, &cv_main.linkedin] {
+        if let Some(url) = present_field(link) {
+            parts.push(format!("{}{}{}", "\\url{", url, "}"));
+        }
+    }
+    if parts.is_empty() {
+        return String::new();
+    }
+    return format!(
+        "{}{}{}",
+        "\\begin{center}\n",
+        parts.join(" | "),
+        "\n\\end{center}\n\n"
+    );
+}
+
+/**
+ * the profile photo float on the right of the header
+ */
+pub fn get_photo_str(photo_path: &Option<String>) -> String {
+    match present_field(photo_path) {
+        Some(path) => {
+            return format!(
+                "{}{}{}",
+                "\\begin{flushright}\n\\vspace{-2.5cm}\n\\includegraphics[height=2.5cm]{",
+                path,
+                "}\n\\end{flushright}\n\n"
+            );
+        }
+        None => return String::new(),
+    }
+}
+
+/**
+ * the moderncv photo, homepage, linkedin and address lines
+ */
+pub fn get_moderncv_contact_str(cv_main: &CvMainResp, photo_path: &Option<String>) -> String {
+    let mut s = String::new();
+    if let Some(path) = present_field(photo_path) {
+        s += &format!("{}{}{}", "\\photo[64pt][0.4pt]{", path, "}\n");
+    }
+    if let Some(homepage) = present_field(&cv_main.homepage) {
+        s += &format!("{}{}{}", "\\homepage{", homepage, "}\n");
+    }
+    if let Some(linkedin) = present_field(&cv_main.linkedin) {
+        // moderncv expect the linkedin account rather than the full url
+        let account = linkedin.trim_end_matches("/").split("/").last().unwrap();
+        s += &format!("{}{}{}", "\\social[linkedin]{", account, "}\n");
+    }
+    if let Some(location) = present_field(&cv_main.location) {
+        s += &format!("{}{}{}", "\\address{", location, "}{}{}\n");
+    }
+    return s;
+}
+
 pub fn gen_work_items(content: String) -> String {
     if content.is_empty() {
         return content;
@@ -109,3 +194,240 @@ pub fn get_project_str(works: &Option<Vec<CvProjectResp>>) -> String {
         None => return "".to_owned(),
     }
 }
+
+pub fn get_edu_str(edus: &Option<Vec<CvEduResp>>) -> String {
+    match edus {
+        Some(edu) => {
+            let mut s = String::new();
+            for i in edu {
+                s += &format!(
+                    "{}{}{}{}{}{}{}{}{}{}{}{}{}",
+                    "\\cventry{",
+                    format_month(&i.admission),
+                    "--",
+                    format_month(&i.graduation),
+                    "}{",
+                    i.degree.as_deref().unwrap_or_default(),
+                    "}{",
+                    i.edu_addr.to_owned(),
+                    "}{",
+                    i.city.as_deref().unwrap_or_default(),
+                    "}{",
+                    i.major.as_deref().unwrap_or_default(),
+                    "}{}\n"
+                )
+                .to_string();
+            }
+            return s;
+        }
+        None => return "".to_owned(),
+    }
+}
+
+pub fn get_lang_str(langs: &Option<Vec<CvLangResp>>) -> String {
+    match langs {
+        Some(lang) => {
+            let mut s = String::new();
+            for i in lang {
+                s += &format!(
+                    "{}{}{}{}{}{}{}",
+                    "\\cvitemwithcomment{",
+                    i.name.clone(),
+                    "}{",
+                    i.level.as_deref().unwrap_or_default(),
+                    "}{",
+                    i.memo.as_deref().unwrap_or_default(),
+                    "}\n"
+                )
+                .to_string();
+            }
+            return s;
+        }
+        None => return "".to_owned(),
+    }
+}
+
+/**
+ * escape the latex special characters in the user text
+ * the `* ` item marker was kept so the item list still work
+ */
+pub fn escape_latex(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => s.push_str("\\textbackslash{}"),
+            '~' => s.push_str("\\textasciitilde{}"),
+            '^' => s.push_str("\\textasciicircum{}"),
+            '{' | '}' | '$' | '&' | '%' | '#' | '_' => {
+                s.push('\\');
+                s.push(c);
+            }
+            _ => s.push(c),
+        }
+    }
+    return s;
+}
+
+/**
+ * the link was put into `\url` or `\href` as is,
+ * drop the characters that could close the argument or start a command
+ */
+fn sanitize_link(link: &str) -> String {
+    return link
+        .chars()
+        .filter(|c| !matches!(c, '\\' | '{' | '}' | '%' | '#'))
+        .collect();
+}
+
+/**
+ * the style options were put into the preamble, keep the plain words only
+ */
+fn sanitize_option(option: &str) -> String {
+    return option
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+}
+
+fn escape_opt(field: &Option<String>) -> Option<String> {
+    return field.as_deref().map(escape_latex);
+}
+
+/**
+ * escape every user field of the cv before handing it to the template generators
+ */
+pub fn escape_cv_main(cv_main: &CvMainResp) -> CvMainResp {
+    let link = |field: &Option<String>| field.as_deref().map(sanitize_link);
+    let option = |field: &Option<String>| field.as_deref().map(sanitize_option);
+    return CvMainResp {
+        cv_name: escape_latex(&cv_main.cv_name),
+        employee_name: escape_opt(&cv_main.employee_name),
+        family_name: escape_opt(&cv_main.family_name),
+        given_name: escape_opt(&cv_main.given_name),
+        birthday: escape_opt(&cv_main.birthday),
+        phone: escape_opt(&cv_main.phone),
+        email: link(&cv_main.email),
+        stackoverflow: link(&cv_main.stackoverflow),
+        github: link(&cv_main.github),
+        blog: link(&cv_main.blog),
+        linkedin: link(&cv_main.linkedin),
+        homepage: link(&cv_main.homepage),
+        location: escape_opt(&cv_main.location),
+        summary: escape_opt(&cv_main.summary),
+        main_color: option(&cv_main.main_color),
+        theme: option(&cv_main.theme),
+        font_size: option(&cv_main.font_size),
+        edu: cv_main.edu.as_ref().map(|edus| {
+            edus.iter()
+                .map(|edu| CvEduResp {
+                    edu_addr: escape_latex(&edu.edu_addr),
+                    degree: escape_opt(&edu.degree),
+                    major: escape_opt(&edu.major),
+                    city: escape_opt(&edu.city),
+                    ..edu.clone()
+                })
+                .collect()
+        }),
+        work: cv_main.work.as_ref().map(|works| {
+            works
+                .iter()
+                .map(|work| CvWorkResp {
+                    company: escape_latex(&work.company),
+                    job: escape_opt(&work.job),
+                    city: escape_opt(&work.city),
+                    duty: escape_opt(&work.duty),
+                    ..work.clone()
+                })
+                .collect()
+        }),
+        skills: cv_main.skills.as_ref().map(|skills| {
+            skills
+                .iter()
+                .map(|skill| CvSkillResp {
+                    name: escape_latex(&skill.name),
+                    memo: escape_opt(&skill.memo),
+                    ..skill.clone()
+                })
+                .collect()
+        }),
+        projects: cv_main.projects.as_ref().map(|projects| {
+            projects
+                .iter()
+                .map(|project| CvProjectResp {
+                    name: escape_latex(&project.name),
+                    company: escape_opt(&project.company),
+                    job: escape_opt(&project.job),
+                    duty: escape_opt(&project.duty),
+                    city: escape_opt(&project.city),
+                    ..project.clone()
+                })
+                .collect()
+        }),
+        langs: cv_main.langs.as_ref().map(|langs| {
+            langs
+                .iter()
+                .map(|lang| CvLangResp {
+                    name: escape_latex(&lang.name),
+                    memo: escape_opt(&lang.memo),
+                    level: escape_opt(&lang.level),
+                    ..lang.clone()
+                })
+                .collect()
+        }),
+        custom_sections: cv_main.custom_sections.as_ref().map(|sections| {
+            sections
+                .iter()
+                .map(|section| CvCustomSectionResp {
+                    title: escape_latex(&section.title),
+                    content: escape_opt(&section.content),
+                    ..section.clone()
+                })
+                .collect()
+        }),
+        ..cv_main.clone()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_latex_escapes_the_special_characters() {
+        assert_eq!(
+            escape_latex("R&D 100% #1 a_b $x$ {y}"),
+            "R\\&D 100\\% \\#1 a\\_b \\$x\\$ \\{y\\}"
+        );
+        assert_eq!(
+            escape_latex("\\input{/etc/passwd}"),
+            "\\textbackslash{}input\\{/etc/passwd\\}"
+        );
+        assert_eq!(escape_latex("~^"), "\\textasciitilde{}\\textasciicircum{}");
+        assert_eq!(escape_latex("* first\n* second"), "* first\n* second");
+    }
+
+    #[test]
+    fn escape_cv_main_escapes_the_nested_items() {
+        let cv_main = CvMainResp {
+            summary: Some("50% done".to_owned()),
+            github: Some("https://github.com/a}\\input{x}".to_owned()),
+            theme: Some("classic}\\input{x".to_owned()),
+            work: Some(vec![CvWorkResp {
+                company: "A&B".to_owned(),
+                duty: Some("* C#".to_owned()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let escaped = escape_cv_main(&cv_main);
+        assert_eq!(escaped.summary.as_deref(), Some("50\\% done"));
+        assert_eq!(
+            escaped.github.as_deref(),
+            Some("https://github.com/ainputx")
+        );
+        assert_eq!(escaped.theme.as_deref(), Some("classicinputx"));
+        let work = &escaped.work.unwrap()[0];
+        assert_eq!(work.company, "A\\&B");
+        assert_eq!(work.duty.as_deref(), Some("* C\\#"));
+    }
+}
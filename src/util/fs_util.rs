@@ -0,0 +1,44 @@
+use std::{fs, path::Path};
+
+/**
+ * recursively copy a directory's contents from `src` to `dst`, the symlinks were ignored
+ */
+pub fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if !dst.exists() {
+        fs::create_dir_all(dst)?;
+    }
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let ty = entry.file_type()?;
+        let dest_path = dst.join(entry.file_name());
+        if ty.is_dir() {
+            copy_dir_all(&entry.path(), &dest_path)?;
+        } else if ty.is_file() {
+            fs::copy(&entry.path(), &dest_path)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn tex_filename_from_path(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(path)
+        .to_string()
+}
+
+/**
+ * create the dir when absent and make sure the process could write into it
+ */
+pub fn ensure_writable_dir(dir: &str) -> Result<(), String> {
+    if let Err(e) = fs::create_dir_all(dir) {
+        return Err(format!("create failed: {}", e));
+    }
+    let probe = format!("{}/.write-probe-{}", dir, std::process::id());
+    if let Err(e) = fs::write(&probe, b"ok") {
+        return Err(format!("not writable: {}", e));
+    }
+    let _ = fs::remove_file(&probe);
+    return Ok(());
+}
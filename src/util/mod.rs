@@ -1 +1,3 @@
-pub mod cv_util;
\ No newline at end of file
+pub mod cv_util;
+pub mod name_util;
+pub mod fs_util;
\ No newline at end of file
@@ -0,0 +1,155 @@
+use crate::model::cv::cv_main::CvMainResp;
+
+/**
+ * the chinese compound surnames, the longer one should be matched before the single
+ * character surname
+ */
+const COMPOUND_SURNAMES: [&str; 64] = [
+    "欧阳", "司马", "上官", "诸葛", "东方", "皇甫", "尉迟", "公孙", "慕容", "长孙", "宇文", "司徒",
+    "令狐", "夏侯", "轩辕", "端木", "独孤", "南宫", "西门", "百里", "呼延", "澹台", "公冶", "宗政",
+    "濮阳", "淳于", "单于", "太叔", "申屠", "万俟", "闻人", "赫连", "钟离", "宰父", "谷梁", "拓跋",
+    "夹谷", "第五", "梁丘", "左丘", "东郭", "司空", "司寇", "亓官", "公西", "羊舌", "微生", "闾丘",
+    "乐正", "漆雕", "壤驷", "巫马", "公良", "颛孙", "仲孙", "东门", "子车", "鲜于", "段干", "太史",
+    "公羊", "即墨", "归海", "呼衍",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CvName {
+    pub family_name: String,
+    pub given_name: String,
+    /// the CJK name was displayed with the family name first
+    pub family_first: bool,
+}
+
+impl CvName {
+    /**
+     * use the explicit family/given name when the user provided them,
+     * fallback to split the employee name
+     */
+    pub fn from_cv(cv_main: &CvMainResp) -> CvName {
+        let family_name = cv_main.family_name.as_deref().unwrap_or_default().trim();
+        let given_name = cv_main.given_name.as_deref().unwrap_or_default().trim();
+        if !family_name.is_empty() || !given_name.is_empty() {
+            return CvName {
+                family_name: family_name.to_owned(),
+                given_name: given_name.to_owned(),
+                family_first: is_cjk_name(family_name) || is_cjk_name(given_name),
+            };
+        }
+        return split_name(cv_main.employee_name.as_deref().unwrap_or_default());
+    }
+
+    pub fn full_name(&self) -> String {
+        let (first, last) = self.display_pair();
+        if self.family_first || first.is_empty() || last.is_empty() {
+            return format!("{}{}", first, last);
+        }
+        return format!("{} {}", first, last);
+    }
+
+    /// the (first, last) name in the display order
+    pub fn display_pair(&self) -> (String, String) {
+        if self.family_first {
+            return (self.family_name.clone(), self.given_name.clone());
+        }
+        return (self.given_name.clone(), self.family_name.clone());
+    }
+}
+
+fn is_cjk_char(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2A6DF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{AC00}'..='\u{D7AF}')
+}
+
+fn is_cjk_name(name: &str) -> bool {
+    name.chars().any(is_cjk_char)
+}
+
+/**
+ * split the full name into family name and given name
+ * CJK name: split at the space when the user put one, otherwise match the compound surname
+ * first and then take the first character
+ * latin name: `Doe, John` or `John Ronald Doe`, the last word was the family name
+ */
+pub fn split_name(name: &str) -> CvName {
+    let trimmed = name.trim();
+    if is_cjk_name(trimmed) {
+        // the user separated the family name explicitly, like `山田 太郎` or `남궁 민`
+        if let Some((family, given)) = trimmed.split_once(char::is_whitespace) {
+            return CvName {
+                family_name: family.to_owned(),
+                given_name: given.trim().to_owned(),
+                family_first: true,
+            };
+        }
+        let surname_len = COMPOUND_SURNAMES
+            .iter()
+            .find(|surname| trimmed.starts_with(*surname) && trimmed.chars().count() > 2)
+            .map_or(1, |surname| surname.chars().count());
+        return CvName {
+            family_name: trimmed.chars().take(surname_len).collect(),
+            given_name: trimmed.chars().skip(surname_len).collect(),
+            family_first: true,
+        };
+    }
+    if let Some((family, given)) = trimmed.split_once(",") {
+        return CvName {
+            family_name: family.trim().to_owned(),
+            given_name: given.trim().to_owned(),
+            family_first: false,
+        };
+    }
+    let words: Vec<&str> = trimmed.split_whitespace().collect();
+    match words.split_last() {
+        Some((last, rest)) if !rest.is_empty() => CvName {
+            family_name: last.to_string(),
+            given_name: rest.join(" "),
+            family_first: false,
+        },
+        _ => CvName {
+            family_name: "".to_owned(),
+            given_name: trimmed.to_owned(),
+            family_first: false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(name: &str) -> (String, String) {
+        let cv_name = split_name(name);
+        return (cv_name.family_name, cv_name.given_name);
+    }
+
+    #[test]
+    fn split_name_uses_the_explicit_space() {
+        assert_eq!(pair("山田 太郎"), ("山田".to_owned(), "太郎".to_owned()));
+        assert_eq!(pair("남궁 민"), ("남궁".to_owned(), "민".to_owned()));
+        assert_eq!(pair(" 司马  相如 "), ("司马".to_owned(), "相如".to_owned()));
+    }
+
+    #[test]
+    fn split_name_matches_surnames_of_unspaced_han_names() {
+        assert_eq!(pair("欧阳娜娜"), ("欧阳".to_owned(), "娜娜".to_owned()));
+        assert_eq!(pair("张三"), ("张".to_owned(), "三".to_owned()));
+        // the two character name was a single surname and a given name
+        assert_eq!(pair("司马"), ("司".to_owned(), "马".to_owned()));
+    }
+
+    #[test]
+    fn split_name_handles_latin_names() {
+        assert_eq!(
+            pair("John Ronald Doe"),
+            ("Doe".to_owned(), "John Ronald".to_owned())
+        );
+        assert_eq!(pair("Doe, John"), ("Doe".to_owned(), "John".to_owned()));
+        assert_eq!(pair("Plato"), ("".to_owned(), "Plato".to_owned()));
+    }
+}